@@ -0,0 +1,79 @@
+//! Benchmarks parsing COLLADA files of increasing size, made by repeating examples/cube.dae
+//! Run with cargo bench; the time per megabyte should stay roughly the same as the input grows
+//! Extra arguments filter the benchmarks by name, like the test harness
+
+use std::time::{Duration, Instant};
+
+/// The sizes of the generated input in megabytes
+const SIZES: [usize; 4] = [1, 2, 4, 8];
+
+/// Builds a document with the body of cube.dae repeated until it is at least size bytes
+fn scaled_cube(size: usize) -> String {
+    let cube = std::fs::read_to_string("examples/cube.dae").expect("Failed to read cube.dae");
+    let start = cube.find("<COLLADA").expect("Missing COLLADA node");
+    let body = &cube[start..];
+
+    let mut xml = String::with_capacity(size + cube.len());
+    xml.push_str("<scene>\n");
+    while xml.len() < size {
+        xml.push_str(body);
+    }
+    xml.push_str("</scene>\n");
+    xml
+}
+
+/// Returns the fastest of a few runs of f
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn bench(name: &str, parse: fn(&str)) {
+    let filter: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|a| !a.starts_with('-'))
+        .collect();
+    if !filter.is_empty() && !filter.iter().any(|f| name.contains(f.as_str())) {
+        return;
+    }
+
+    for &megabytes in SIZES.iter() {
+        let xml = scaled_cube(megabytes << 20);
+        let elapsed = time(|| parse(&xml));
+        let per_mb = elapsed.as_secs_f64() * 1000.0 / (xml.len() as f64 / (1 << 20) as f64);
+        println!(
+            "{:<24} {:>2} MB {:>10.2?} {:>8.2} ms/MB",
+            name, megabytes, elapsed, per_mb
+        );
+    }
+}
+
+fn main() {
+    bench("from_string", |xml| {
+        szl_simple_xml::from_string(xml).expect("Failed to parse");
+    });
+    bench("from_string_namespaces", |xml| {
+        let options = szl_simple_xml::ParseOptions {
+            namespaces: true,
+            ..Default::default()
+        };
+        szl_simple_xml::from_string_with(xml, options).expect("Failed to parse");
+    });
+    bench("reader", |xml| {
+        for event in szl_simple_xml::reader::Reader::new(xml.as_bytes()) {
+            event.expect("Failed to read");
+        }
+    });
+    bench("from_string_recovering", |xml| {
+        // Unclosed nodes make every error position be calculated
+        let xml = xml.replace("</p>", "");
+        let (_, errors) = szl_simple_xml::from_string_recovering(&xml, Default::default());
+        assert!(!errors.is_empty());
+    });
+}
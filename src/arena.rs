@@ -0,0 +1,323 @@
+//! This is a module providing an arena representation of an xml tree, where nodes are addressed by NodeId
+//! Unlike Node, every node knows its parent and siblings, so the tree can be walked in any direction
+//! ```
+//! use szl_simple_xml::arena::Arena;
+//!
+//! let root = szl_simple_xml::from_string("<mesh><geometry id='cube'><source/></geometry></mesh>").unwrap();
+//! let arena = Arena::from(&root);
+//! let source = arena.descendants(arena.root()).find(|&id| arena[id].tag == "source").unwrap();
+//! let geometry = arena.ancestors(source).find(|&id| arena[id].tag == "geometry").unwrap();
+//! assert_eq!(arena[geometry].get_attribute("id").unwrap(), "cube");
+//! ```
+
+use crate::namespace::Scope;
+use crate::parser::Tree;
+use crate::{Attributes, Item, Node};
+use std::borrow::Cow;
+use std::ops;
+
+/// The index of a node in an arena
+/// Ids are ordered by the position of the nodes in the document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// A part of a node in document order
+#[derive(Debug, Clone)]
+enum Part {
+    /// The next of the child nodes
+    Node,
+    Text(String),
+    CData(String),
+    Comment(String),
+}
+
+/// A node stored in an arena
+#[derive(Debug, Clone)]
+pub struct ArenaNode {
+    pub tag: String,
+    pub attributes: Attributes,
+    pub content: String,
+    /// The child nodes, text and comments in document order
+    parts: Vec<Part>,
+    namespaces: Option<Scope>,
+}
+
+impl ArenaNode {
+    /// Returns the value of an attribute or None if it doesn't exist
+    pub fn get_attribute(&self, key: &str) -> Option<&String> {
+        self.attributes.get(key)
+    }
+}
+
+/// An xml tree stored as a list of nodes in document order
+#[derive(Debug, Clone)]
+pub struct Arena {
+    nodes: Links<ArenaNode>,
+}
+
+impl Arena {
+    /// Returns the id of the root node
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Returns the node with the given id
+    /// Panics if the id is from another arena and out of range
+    pub fn get(&self, id: NodeId) -> &ArenaNode {
+        self.nodes.get(id.0)
+    }
+
+    /// Returns the node with the given id mutably
+    pub fn get_mut(&mut self, id: NodeId) -> &mut ArenaNode {
+        self.nodes.get_mut(id.0)
+    }
+
+    /// Returns the parent of a node, or None for the root
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes.parent(id.0).map(NodeId)
+    }
+
+    /// Returns the child nodes of a node in document order
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes.children(id.0).iter().copied().map(NodeId)
+    }
+
+    /// Returns the node after this one with the same parent
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes
+            .following_siblings(id.0)
+            .first()
+            .copied()
+            .map(NodeId)
+    }
+
+    /// Returns the node before this one with the same parent
+    pub fn prev_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes
+            .preceding_siblings(id.0)
+            .last()
+            .copied()
+            .map(NodeId)
+    }
+
+    /// Returns the parent of a node, its parent and so on up to the root
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes.ancestors(id.0).map(NodeId)
+    }
+
+    /// Returns every node inside a node in document order, not including the node itself
+    pub fn descendants(&self, id: NodeId) -> impl Iterator<Item = NodeId> {
+        (id.0 + 1..self.nodes.end(id.0)).map(NodeId)
+    }
+
+    /// Copies a node and everything inside it into an owned Node
+    pub fn to_node(&self, id: NodeId) -> Node {
+        // An explicit stack of the nodes being copied, their next part and their next child
+        let mut stack = vec![(id, 0, 0, self.copy_node(id))];
+        loop {
+            let (id, part, child, node) = stack.last_mut().unwrap();
+            match self[*id].parts.get(*part) {
+                Some(v) => {
+                    *part += 1;
+                    match v {
+                        Part::Node => {
+                            let child_id = NodeId(self.nodes.children(id.0)[*child]);
+                            *child += 1;
+                            stack.push((child_id, 0, 0, self.copy_node(child_id)));
+                        }
+                        Part::Text(text) => node.push_text(Cow::Borrowed(text)),
+                        Part::CData(data) => node.push_cdata(data),
+                        Part::Comment(comment) => node.push_comment(comment),
+                    }
+                }
+                None => {
+                    let (id, _, _, mut node) = stack.pop().unwrap();
+                    // Content which was changed in the arena replaces the text parts
+                    if node.content != self[id].content {
+                        node.content = self[id].content.clone();
+                    }
+                    match stack.last_mut() {
+                        Some((_, _, _, parent)) => parent.push_node(node),
+                        None => return node,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies the tag, attributes and namespaces of a node into an owned node without children
+    fn copy_node(&self, id: NodeId) -> Node {
+        let source = &self[id];
+        let mut node = crate::new(&source.tag, String::new());
+        node.attributes = source.attributes.clone();
+        node.namespaces = source.namespaces.clone();
+        node
+    }
+
+    /// Adds a node without children as the last child of parent
+    fn push(&mut self, node: &Node, parent: Option<NodeId>) -> NodeId {
+        if let Some(parent) = parent {
+            self[parent].parts.push(Part::Node);
+        }
+        let node = ArenaNode {
+            tag: node.tag.clone(),
+            attributes: node.attributes.clone(),
+            content: node.content.clone(),
+            parts: Vec::new(),
+            namespaces: node.namespaces.clone(),
+        };
+        NodeId(self.nodes.push(node, parent.map(|id| id.0)))
+    }
+}
+
+impl From<&Node> for Arena {
+    /// Copies a tree into an arena, keeping the document order of its items
+    fn from(node: &Node) -> Self {
+        let mut arena = Arena {
+            nodes: Links::new(),
+        };
+        let root = arena.push(node, None);
+
+        let mut stack = vec![(root, node.items())];
+        while let Some((id, items)) = stack.last_mut() {
+            let id = *id;
+            let part = match items.next() {
+                Some(Item::Node(child)) => {
+                    let child_id = arena.push(child, Some(id));
+                    stack.push((child_id, child.items()));
+                    continue;
+                }
+                Some(Item::Text(text)) => Part::Text(text.to_owned()),
+                Some(Item::CData(data)) => Part::CData(data.to_owned()),
+                Some(Item::Comment(comment)) => Part::Comment(comment.to_owned()),
+                None => {
+                    arena.nodes.close(id.0);
+                    stack.pop();
+                    continue;
+                }
+            };
+            arena[id].parts.push(part);
+        }
+        arena
+    }
+}
+
+impl From<&Arena> for Node {
+    /// Copies the whole arena into an owned tree
+    fn from(arena: &Arena) -> Self {
+        arena.to_node(arena.root())
+    }
+}
+
+impl ops::Index<NodeId> for Arena {
+    type Output = ArenaNode;
+    fn index(&self, id: NodeId) -> &Self::Output {
+        self.get(id)
+    }
+}
+
+impl ops::IndexMut<NodeId> for Arena {
+    fn index_mut(&mut self, id: NodeId) -> &mut Self::Output {
+        self.get_mut(id)
+    }
+}
+
+/// A value in Links with its place in the tree
+#[derive(Debug, Clone)]
+struct Link<T> {
+    value: T,
+    parent: Option<usize>,
+    /// The position among the children of the parent
+    index: usize,
+    children: Vec<usize>,
+    /// The id after the last descendant
+    end: usize,
+}
+
+/// The values of a tree in document order with links to their parents and children
+/// Values are pushed in document order and closed once everything inside them is pushed, so the
+/// descendants of a value are the ids up to its end
+#[derive(Debug, Clone)]
+pub(crate) struct Links<T> {
+    links: Vec<Link<T>>,
+}
+
+impl<T> Links<T> {
+    pub(crate) fn new() -> Self {
+        Links { links: Vec::new() }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Adds a value as the last child of parent and returns its id
+    pub(crate) fn push(&mut self, value: T, parent: Option<usize>) -> usize {
+        let id = self.links.len();
+        let index = match parent {
+            Some(parent) => {
+                let children = &mut self.links[parent].children;
+                children.push(id);
+                children.len() - 1
+            }
+            None => 0,
+        };
+        self.links.push(Link {
+            value,
+            parent,
+            index,
+            children: Vec::new(),
+            end: id + 1,
+        });
+        id
+    }
+
+    /// Marks everything pushed since a value as inside it
+    pub(crate) fn close(&mut self, id: usize) {
+        self.links[id].end = self.links.len();
+    }
+
+    pub(crate) fn get(&self, id: usize) -> &T {
+        &self.links[id].value
+    }
+
+    pub(crate) fn get_mut(&mut self, id: usize) -> &mut T {
+        &mut self.links[id].value
+    }
+
+    pub(crate) fn parent(&self, id: usize) -> Option<usize> {
+        self.links[id].parent
+    }
+
+    pub(crate) fn children(&self, id: usize) -> &[usize] {
+        &self.links[id].children
+    }
+
+    /// Returns the id after the last descendant of a value
+    pub(crate) fn end(&self, id: usize) -> usize {
+        self.links[id].end
+    }
+
+    /// Returns the children of the parent after this value
+    pub(crate) fn following_siblings(&self, id: usize) -> &[usize] {
+        let link = &self.links[id];
+        match link.parent {
+            Some(parent) => &self.links[parent].children[link.index + 1..],
+            None => &[],
+        }
+    }
+
+    /// Returns the children of the parent before this value
+    pub(crate) fn preceding_siblings(&self, id: usize) -> &[usize] {
+        let link = &self.links[id];
+        match link.parent {
+            Some(parent) => &self.links[parent].children[..link.index],
+            None => &[],
+        }
+    }
+
+    /// Returns the parent of a value, its parent and so on up to the root
+    pub(crate) fn ancestors(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.parent(id), move |&id| self.parent(id))
+    }
+}
@@ -0,0 +1,264 @@
+//! This is a module providing async parsing and saving with tokio, enabled by the tokio feature
+//! Reading and writing never block the executor
+//! ```
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! use szl_simple_xml::async_io::AsyncReader;
+//! use szl_simple_xml::reader::Event;
+//!
+//! let mut reader = AsyncReader::new("<note><to>Tove</to></note>".as_bytes());
+//! let mut names = Vec::new();
+//! loop {
+//!     match reader.next_event().await.unwrap() {
+//!         Event::StartElement { name, .. } => names.push(name),
+//!         Event::Eof => break,
+//!         _ => {}
+//!     }
+//! }
+//! assert_eq!(names, vec!["note", "to"]);
+//! # }
+//! ```
+
+use crate::push::{Nodes, Step};
+use crate::reader::{Event, State};
+use crate::{Document, Error, Misc, Node, ParseOptions, Position, WriteOptions};
+use std::io;
+use std::path::Path;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The number of bytes read at a time
+const CHUNK: usize = 8192;
+
+/// Loads an xml structure from an async reader
+pub async fn from_async_reader<R: AsyncRead + Unpin>(reader: R) -> Result<Node, Error> {
+    from_async_reader_with(reader, ParseOptions::default()).await
+}
+
+/// Loads an xml structure from an async reader using the given parse options
+pub async fn from_async_reader_with<R: AsyncRead + Unpin>(
+    reader: R,
+    options: ParseOptions,
+) -> Result<Node, Error> {
+    Document::from_async_reader_with(reader, options)
+        .await
+        .map(|document| document.root)
+}
+
+/// Reads events from xml in an async reader
+/// Works like reader::Reader, see it for details
+pub struct AsyncReader<R: AsyncRead + Unpin> {
+    reader: R,
+    state: State,
+    /// The bytes read but not pushed yet, kept between reads
+    chunk: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncReader<R> {
+    /// Creates a reader with the default parse options
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParseOptions::default())
+    }
+
+    /// Creates a reader using the given parse options
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        AsyncReader {
+            reader,
+            state: State::new(options),
+            chunk: Vec::new(),
+        }
+    }
+
+    /// Returns the depth of the current node, the number of nodes which are open
+    pub fn depth(&self) -> usize {
+        self.state.depth()
+    }
+
+    /// Returns the position of the next unread input
+    pub fn position(&self) -> Position {
+        self.state.position()
+    }
+
+    /// Reads the next event
+    /// After an error, Eof is returned
+    pub async fn next_event(&mut self) -> Result<Event, Error> {
+        loop {
+            if let Some(event) = self.state.next_event()? {
+                return Ok(event);
+            }
+            if let Err(e) = self.fill().await {
+                self.state.done = true;
+                return Err(e);
+            }
+        }
+    }
+
+    /// Reads more input, see Reader::fill
+    async fn fill(&mut self) -> Result<(), Error> {
+        let want = self.state.unread().max(CHUNK);
+        self.chunk.resize(want, 0);
+        let mut read = 0;
+        while read < want {
+            let n = self.reader.read(&mut self.chunk).await?;
+            if n == 0 {
+                return self.state.finish();
+            }
+            read += n;
+            self.state.push(&self.chunk[..n])?;
+
+            // A small token only needs one read
+            if want == CHUNK {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Document {
+    /// Loads a document from an async reader
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(reader: R) -> Result<Self, Error> {
+        Self::from_async_reader_with(reader, ParseOptions::default()).await
+    }
+
+    /// Loads a document from an async reader using the given parse options
+    pub async fn from_async_reader_with<R: AsyncRead + Unpin>(
+        reader: R,
+        options: ParseOptions,
+    ) -> Result<Self, Error> {
+        let mut reader = AsyncReader::with_options(reader, options);
+        reader.state.raw_text = true;
+        let mut document = Document::new(crate::new("", String::new()));
+        // The version is only set by a declaration
+        document.version = None;
+        let mut nodes = Nodes::default();
+        let mut root = None;
+
+        loop {
+            let misc = match nodes.event(reader.next_event().await?, &reader.state) {
+                Step::Added => continue,
+                Step::Closed(node) => {
+                    root = Some(node);
+                    continue;
+                }
+                Step::Outside(Event::ProcessingInstruction(target, data)) => {
+                    let declaration = match target.as_str() {
+                        "xml" => reader.state.declaration.take(),
+                        _ => None,
+                    };
+                    match declaration {
+                        Some(declaration) => {
+                            document.version = declaration.version;
+                            document.encoding = declaration.encoding;
+                            document.standalone = declaration.standalone;
+                            continue;
+                        }
+                        None => Misc::ProcessingInstruction(target, data),
+                    }
+                }
+                Step::Outside(Event::Comment(comment)) => Misc::Comment(comment),
+                Step::Outside(Event::Doctype(doctype)) => {
+                    document.doctype = Some(doctype);
+                    continue;
+                }
+                Step::Outside(Event::Eof) => break,
+                Step::Outside(_) => continue,
+            };
+            match root {
+                None => document.prolog.push(misc),
+                Some(_) => document.epilog.push(misc),
+            }
+        }
+
+        if let Some(root) = root {
+            document.root = root;
+        }
+        Ok(document)
+    }
+
+    /// Writes the document to an async writer using the given write options
+    pub async fn write_async<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        options: WriteOptions,
+    ) -> io::Result<()> {
+        writer
+            .write_all(self.to_string_with(options).as_bytes())
+            .await?;
+        writer.flush().await
+    }
+
+    /// This writes the document to a file specified by path without blocking
+    /// Uses the non-pretty to_string formatting
+    pub async fn save_to_file_async<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save_to_file_with_async(path, WriteOptions::default())
+            .await
+    }
+
+    /// This writes the document to a file specified by path without blocking
+    /// Uses the pretty to_string_pretty formatting
+    pub async fn save_to_file_pretty_async<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save_to_file_with_async(
+            path,
+            WriteOptions {
+                pretty: true,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// This writes the document to a file specified by path using the given write options without blocking
+    pub async fn save_to_file_with_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: WriteOptions,
+    ) -> io::Result<()> {
+        let mut file = tokio::fs::File::create(path).await?;
+        self.write_async(&mut file, options).await
+    }
+}
+
+impl Node {
+    /// Writes the xml structure to an async writer using the given write options
+    pub async fn write_async<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        options: WriteOptions,
+    ) -> io::Result<()> {
+        writer
+            .write_all(self.to_string_with(options).as_bytes())
+            .await?;
+        writer.flush().await
+    }
+
+    /// This writes an xml structure to a file specified by path without blocking
+    /// Uses the non-pretty to_string formatting
+    pub async fn save_to_file_async<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save_to_file_with_async(path, WriteOptions::default())
+            .await
+    }
+
+    /// This writes an xml structure to a file specified by path without blocking
+    /// Uses the pretty to_string_pretty formatting
+    pub async fn save_to_file_pretty_async<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save_to_file_with_async(
+            path,
+            WriteOptions {
+                pretty: true,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// This writes an xml structure to a file specified by path using the given write options
+    /// without blocking
+    pub async fn save_to_file_with_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: WriteOptions,
+    ) -> io::Result<()> {
+        let mut file = tokio::fs::File::create(path).await?;
+        self.write_async(&mut file, options).await
+    }
+}
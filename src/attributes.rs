@@ -0,0 +1,122 @@
+//! This is a module providing an attribute map which remembers insertion order
+//! Attributes are written in the order they were parsed or added, so saving a file twice gives the same output
+
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use std::ops;
+
+/// The attributes of a node as key value pairs in insertion order
+/// Lookups are linear, which is faster than hashing for the handful of attributes a node usually has
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Attributes {
+    pairs: Vec<(String, String)>,
+}
+
+impl Attributes {
+    /// Creates an empty attribute map
+    pub fn new() -> Self {
+        Attributes { pairs: Vec::new() }
+    }
+
+    /// Returns the value of an attribute or None if it doesn't exist
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Returns a mutable reference to the value of an attribute or None if it doesn't exist
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut String> {
+        self.pairs
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Returns true if an attribute with the key exists
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Adds or updates an attribute
+    /// An updated attribute keeps its position and the old value is returned
+    pub fn insert(&mut self, key: String, val: String) -> Option<String> {
+        match self.get_mut(&key) {
+            Some(v) => Some(std::mem::replace(v, val)),
+            None => {
+                self.pairs.push((key, val));
+                None
+            }
+        }
+    }
+
+    /// Removes an attribute and returns its value, keeping the order of the other attributes
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let i = self.pairs.iter().position(|(k, _)| k == key)?;
+        Some(self.pairs.remove(i).1)
+    }
+
+    /// Returns the number of attributes
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Returns true if there are no attributes
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Iterates the attributes as key value pairs in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.pairs.iter().map(|(k, v)| (k, v))
+    }
+
+    /// Iterates the attribute keys in insertion order
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.pairs.iter().map(|(k, _)| k)
+    }
+
+    /// Iterates the attribute values in insertion order
+    pub fn values(&self) -> impl Iterator<Item = &String> {
+        self.pairs.iter().map(|(_, v)| v)
+    }
+
+    /// Sorts the attributes alphabetically by key
+    pub fn sort(&mut self) {
+        self.pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+}
+
+/// Returns the value of an attribute
+/// Panics if the attribute doesn't exist, like HashMap
+impl ops::Index<&str> for Attributes {
+    type Output = String;
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get(key).expect("attribute not found")
+    }
+}
+
+impl FromIterator<(String, String)> for Attributes {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        let mut attributes = Attributes::new();
+        for (k, v) in iter {
+            attributes.insert(k, v);
+        }
+        attributes
+    }
+}
+
+/// A HashMap has no order, so the attributes are sorted by key
+impl From<HashMap<String, String>> for Attributes {
+    fn from(map: HashMap<String, String>) -> Self {
+        let mut attributes: Attributes = map.into_iter().collect();
+        attributes.sort();
+        attributes
+    }
+}
+
+impl IntoIterator for Attributes {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
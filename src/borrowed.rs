@@ -0,0 +1,269 @@
+//! This is a module providing a tree which borrows from the parsed input instead of copying it
+//! Tags, attribute keys, CDATA sections and comments always point into the input, while text and
+//! attribute values are only allocated when entities have to be decoded
+//! ```
+//! use std::borrow::Cow;
+//!
+//! let xml = "<note lang=\"en\"><to>Tove</to><body>Fish &amp; chips</body></note>";
+//! let note = szl_simple_xml::from_string_borrowed(xml).unwrap();
+//! assert_eq!(note.get_attribute("lang"), Some("en"));
+//! assert!(matches!(note.get_nodes("to")[0].content(), Cow::Borrowed("Tove")));
+//! assert_eq!(note.get_nodes("body")[0].content(), "Fish & chips");
+//!
+//! let owned = note.to_owned();
+//! assert_eq!(owned["body"][0].content, "Fish & chips");
+//! ```
+
+use crate::namespace::Scope;
+use crate::parser::{layout, Child, Tree};
+use crate::Node;
+use std::borrow::Cow;
+use std::fmt;
+
+/// A node which references the input it was parsed from
+/// Namespaces are checked when parsing with the namespaces option, but not kept in the tree
+pub struct BorrowedNode<'a> {
+    pub tag: &'a str,
+    /// The attributes in document order, whose values only own their data if they contained entities
+    pub attributes: Vec<(&'a str, Cow<'a, str>)>,
+    /// The child nodes, text, CDATA sections and comments in document order
+    pub items: Vec<BorrowedItem<'a>>,
+}
+
+/// A child of a borrowed node
+#[derive(Debug, Clone, PartialEq)]
+pub enum BorrowedItem<'a> {
+    Node(BorrowedNode<'a>),
+    /// A run of text, which only owns its data if it contained entities
+    /// Whitespace is removed as for Item::Text
+    Text(Cow<'a, str>),
+    CData(&'a str),
+    Comment(&'a str),
+}
+
+impl<'a> BorrowedNode<'a> {
+    /// Returns the value of an attribute or None if it doesn't exist
+    pub fn get_attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_ref())
+    }
+
+    /// Returns the child nodes in document order
+    pub fn nodes(&self) -> impl Iterator<Item = &BorrowedNode<'a>> {
+        self.items.iter().filter_map(|item| match item {
+            BorrowedItem::Node(node) => Some(node),
+            _ => None,
+        })
+    }
+
+    /// Returns the child nodes with the given tag in document order
+    /// If no such nodes exist, the returned vector is empty
+    pub fn get_nodes(&self, tag: &str) -> Vec<&BorrowedNode<'a>> {
+        self.nodes().filter(|node| node.tag == tag).collect()
+    }
+
+    /// Returns the text and CDATA sections of the node joined together, like Node::content
+    /// Only allocates if there is more than one piece, or the text contained entities
+    pub fn content(&self) -> Cow<'_, str> {
+        let mut pieces = self.items.iter().filter_map(|item| match item {
+            BorrowedItem::Text(text) => Some(text.as_ref()),
+            BorrowedItem::CData(data) => Some(*data),
+            _ => None,
+        });
+
+        let first = match pieces.next() {
+            Some(v) => v,
+            None => return Cow::Borrowed(""),
+        };
+        match pieces.next() {
+            Some(second) => {
+                let mut content = String::from(first);
+                content.push_str(second);
+                pieces.for_each(|piece| content.push_str(piece));
+                Cow::Owned(content)
+            }
+            None => Cow::Borrowed(first),
+        }
+    }
+
+    /// Copies the tree into an owned Node, which no longer references the input
+    pub fn to_owned(&self) -> Node {
+        // An explicit stack of the nodes being copied and their next item
+        let mut stack = vec![(self, 0, self.copy_node())];
+        loop {
+            let (source, i, node) = stack.last_mut().unwrap();
+            let source: &BorrowedNode = source;
+            match source.items.get(*i) {
+                Some(item) => {
+                    *i += 1;
+                    match item {
+                        BorrowedItem::Node(child) => stack.push((child, 0, child.copy_node())),
+                        BorrowedItem::Text(text) => node.push_text(Cow::Borrowed(text)),
+                        BorrowedItem::CData(data) => node.push_cdata(data),
+                        BorrowedItem::Comment(comment) => node.push_comment(comment),
+                    }
+                }
+                None => {
+                    let (_, _, node) = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some((_, _, parent)) => parent.push_node(node),
+                        None => return node,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies the tag and attributes into an owned node without children
+    fn copy_node(&self) -> Node {
+        let mut node = crate::new(self.tag, String::new());
+        node.set_attributes(self.attributes.clone(), None);
+        node
+    }
+
+    /// Copies the tag and attributes into a node without children
+    fn copy_empty(&self) -> Self {
+        BorrowedNode {
+            tag: self.tag,
+            attributes: self.attributes.clone(),
+            items: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Tree<'a> for BorrowedNode<'a> {
+    fn open(tag: &'a str) -> Self {
+        BorrowedNode {
+            tag,
+            attributes: Vec::new(),
+            items: Vec::new(),
+        }
+    }
+
+    fn set_attributes(&mut self, attributes: Vec<(&'a str, Cow<'a, str>)>, _scope: Option<Scope>) {
+        self.attributes = attributes;
+    }
+
+    fn tag(&self) -> &str {
+        self.tag
+    }
+
+    fn push_text(&mut self, text: Cow<'a, str>) {
+        self.items.push(BorrowedItem::Text(text));
+    }
+
+    fn push_cdata(&mut self, data: &'a str) {
+        self.items.push(BorrowedItem::CData(data));
+    }
+
+    fn push_comment(&mut self, comment: &'a str) {
+        self.items.push(BorrowedItem::Comment(comment));
+    }
+
+    fn push_node(&mut self, node: Self) {
+        self.items.push(BorrowedItem::Node(node));
+    }
+
+    fn trim(&mut self) {
+        let children: Vec<_> = self
+            .items
+            .iter()
+            .map(|item| match item {
+                BorrowedItem::Node(_) => Child::Node,
+                BorrowedItem::Text(text) => Child::Text(text),
+                BorrowedItem::CData(_) => Child::CData,
+                BorrowedItem::Comment(_) => Child::Comment,
+            })
+            .collect();
+        let mut ranges = layout(&children).into_iter();
+
+        for item in &mut self.items {
+            if let BorrowedItem::Text(text) = item {
+                let range = ranges.next().unwrap();
+                match text {
+                    Cow::Borrowed(v) => *v = &v[range],
+                    Cow::Owned(v) => {
+                        v.truncate(range.end);
+                        v.drain(..range.start);
+                    }
+                }
+            }
+        }
+        self.items
+            .retain(|item| !matches!(item, BorrowedItem::Text(text) if text.is_empty()));
+    }
+}
+
+/// Drops the child nodes one at a time instead of recursively, like Node
+impl Drop for BorrowedNode<'_> {
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.items);
+        while let Some(item) = stack.pop() {
+            if let BorrowedItem::Node(mut node) = item {
+                stack.append(&mut node.items);
+            }
+        }
+    }
+}
+
+/// Copies the child nodes one at a time instead of recursively, like to_owned
+impl Clone for BorrowedNode<'_> {
+    fn clone(&self) -> Self {
+        let mut stack = vec![(self, 0, self.copy_empty())];
+        loop {
+            let (source, i, node) = stack.last_mut().unwrap();
+            let source: &BorrowedNode = source;
+            match source.items.get(*i) {
+                Some(item) => {
+                    *i += 1;
+                    match item {
+                        BorrowedItem::Node(child) => stack.push((child, 0, child.copy_empty())),
+                        item => node.items.push(item.clone()),
+                    }
+                }
+                None => {
+                    let (_, _, node) = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some((_, _, parent)) => parent.items.push(BorrowedItem::Node(node)),
+                        None => return node,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Compares the child nodes one pair at a time instead of recursively
+impl PartialEq for BorrowedNode<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((a, b)) = stack.pop() {
+            if a.tag != b.tag || a.attributes != b.attributes || a.items.len() != b.items.len() {
+                return false;
+            }
+            for pair in a.items.iter().zip(&b.items) {
+                match pair {
+                    (BorrowedItem::Node(a), BorrowedItem::Node(b)) => stack.push((a, b)),
+                    (a, b) if a != b => return false,
+                    _ => {}
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Child nodes are listed by tag only, like for Node
+impl fmt::Debug for BorrowedNode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let children: Vec<_> = self.nodes().map(|node| node.tag).collect();
+        f.debug_struct("BorrowedNode")
+            .field("tag", &self.tag)
+            .field("attributes", &self.attributes)
+            .field("content", &self.content())
+            .field("children", &children)
+            .finish()
+    }
+}
@@ -0,0 +1,111 @@
+//! This is a module providing the checks shared by the tree builder and the reader
+//! Both read the same tokens, so entities, attributes, namespaces and closing tags are checked here
+//! Errors are passed to a report function along with the subslice of the input they are positioned at
+//! If it returns Ok, checking carries on past the error as when recovering
+
+use crate::namespace::{self, Scope};
+use crate::{escape, tokenizer, Error, ParseError, ParseOptions};
+use std::borrow::Cow;
+
+/// Decodes the entities in a piece of text unless raw parsing was requested
+/// When carrying on, invalid entities are kept as they appear in the source
+pub(crate) fn decode<'a>(
+    string: &'a str,
+    options: &ParseOptions,
+    report: &mut impl FnMut(ParseError, &str) -> Result<(), Error>,
+) -> Result<Cow<'a, str>, Error> {
+    if options.raw {
+        return Ok(Cow::Borrowed(string));
+    }
+
+    let mut result = String::new();
+    let mut rest = string;
+    loop {
+        let e = match escape::unescape(rest) {
+            Ok(v) if rest.len() == string.len() => return Ok(v),
+            Ok(v) => {
+                result.push_str(&v);
+                return Ok(Cow::Owned(result));
+            }
+            Err(e) => e,
+        };
+
+        // Everything before the first invalid entity decodes fine
+        let start = rest.find(&e).unwrap_or(0);
+        report(ParseError::InvalidEntity(e.clone()), &rest[start..])?;
+        result.push_str(&escape::unescape(&rest[..start]).unwrap_or_default());
+        result.push_str(&e);
+        rest = &rest[start + e.len()..];
+    }
+}
+
+/// Returns the decoded attributes of a tag in the order they appear
+/// When carrying on, attributes without a value are skipped
+/// A repeated attribute keeps its first position and its last value
+pub(crate) fn attributes<'a>(
+    raw: &'a str,
+    options: &ParseOptions,
+    report: &mut impl FnMut(ParseError, &str) -> Result<(), Error>,
+) -> Result<Vec<(&'a str, Cow<'a, str>)>, Error> {
+    let mut attributes: Vec<(&'a str, Cow<'a, str>)> = Vec::new();
+    for attribute in tokenizer::attributes(raw) {
+        if let Some(e) = attribute.error {
+            let skip = matches!(e, ParseError::MissingAttributeValue(_));
+            report(e, attribute.part)?;
+            if skip {
+                continue;
+            }
+        }
+        let (key, value) = (attribute.key, decode(attribute.value, options, report)?);
+        match attributes.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => attributes.push((key, value)),
+        }
+    }
+    Ok(attributes)
+}
+
+/// Returns the namespace scope of a node inside the scope of its parent
+/// A prefix which is not declared is reported at the start of the tag, given by at
+pub(crate) fn scope(
+    name: &str,
+    attributes: &[(&str, Cow<str>)],
+    parent: Option<Scope>,
+    at: &str,
+    report: &mut impl FnMut(ParseError, &str) -> Result<(), Error>,
+) -> Result<Scope, Error> {
+    let pairs = attributes.iter().map(|(k, v)| (*k, v.as_ref()));
+    let scope = namespace::resolve_scope(pairs, &parent.unwrap_or_default());
+    let keys = attributes.iter().map(|(k, _)| *k);
+    if let Err(e) = namespace::check_prefixes(name, keys, &scope) {
+        report(e, at)?;
+    }
+    Ok(scope)
+}
+
+/// Returns the number of open nodes a closing tag closes, counting from the innermost
+/// open yields the tags of the open nodes from the outermost
+/// More than one means the nodes opened inside the matching node were never closed
+pub(crate) fn closes<'a>(
+    open: impl DoubleEndedIterator<Item = &'a str>,
+    name: &str,
+) -> Result<usize, ParseError> {
+    open.rev()
+        .position(|tag| tag == name)
+        .map(|i| i + 1)
+        .ok_or_else(|| ParseError::UnexpectedClosingTag(name.to_owned()))
+}
+
+/// Returns true if a processing instruction is an xml declaration
+pub(crate) fn is_declaration(pi: &str) -> bool {
+    processing_instruction(pi).0 == "xml"
+}
+
+/// Splits a processing instruction into its target and data
+pub(crate) fn processing_instruction(pi: &str) -> (&str, &str) {
+    let pi = pi.trim();
+    match pi.find(char::is_whitespace) {
+        Some(i) => (&pi[..i], pi[i..].trim()),
+        None => (pi, ""),
+    }
+}
@@ -0,0 +1,83 @@
+//! This is a module providing typed access to attributes and content through FromStr
+//! Surrounding whitespace is ignored, and lists are separated by whitespace as in COLLADA files
+//! ```
+//! let cube = szl_simple_xml::from_file("examples/cube.dae").unwrap();
+//! let xfov: f32 = cube.at("library_cameras/camera/optics/technique_common/perspective/xfov")
+//!     .unwrap()
+//!     .content_as()
+//!     .unwrap();
+//! assert_eq!(xfov, 39.59775);
+//!
+//! let color = cube.at("library_lights/light/technique_common/point/color").unwrap();
+//! assert_eq!(color.try_content_as_list::<u32>().unwrap(), vec![1000, 1000, 1000]);
+//! ```
+
+use crate::{Error, Node};
+use std::str::FromStr;
+
+impl Node {
+    /// Returns an attribute converted to T
+    /// If the attribute doesn't exist or can't be converted, None is returned
+    pub fn attr_as<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.try_attr_as(key).ok()
+    }
+
+    /// Returns an attribute converted to T
+    /// If the attribute doesn't exist, an Err of AttributeNotFound is returned
+    /// If it can't be converted, an Err of InvalidValue is returned containing the tag, key and value
+    pub fn try_attr_as<T: FromStr>(&self, key: &str) -> Result<T, Error> {
+        self.convert(Some(key), self.try_get_attribute(key)?)
+    }
+
+    /// Returns the content converted to T
+    /// If it can't be converted, None is returned
+    pub fn content_as<T: FromStr>(&self) -> Option<T> {
+        self.try_content_as().ok()
+    }
+
+    /// Returns the content converted to T
+    /// If it can't be converted, an Err of InvalidValue is returned containing the tag and content
+    pub fn try_content_as<T: FromStr>(&self) -> Result<T, Error> {
+        self.convert(None, &self.content)
+    }
+
+    /// Returns the whitespace separated values of an attribute converted to T
+    /// If the attribute doesn't exist or any value can't be converted, None is returned
+    pub fn attr_as_list<T: FromStr>(&self, key: &str) -> Option<Vec<T>> {
+        self.try_attr_as_list(key).ok()
+    }
+
+    /// Returns the whitespace separated values of an attribute converted to T
+    /// If the attribute doesn't exist, an Err of AttributeNotFound is returned
+    /// If a value can't be converted, an Err of InvalidValue is returned containing that value
+    pub fn try_attr_as_list<T: FromStr>(&self, key: &str) -> Result<Vec<T>, Error> {
+        self.convert_list(Some(key), self.try_get_attribute(key)?)
+    }
+
+    /// Returns the whitespace separated values of the content converted to T, such as the numbers
+    /// of a float_array
+    /// If any value can't be converted, None is returned
+    pub fn content_as_list<T: FromStr>(&self) -> Option<Vec<T>> {
+        self.try_content_as_list().ok()
+    }
+
+    /// Returns the whitespace separated values of the content converted to T
+    /// If a value can't be converted, an Err of InvalidValue is returned containing that value
+    pub fn try_content_as_list<T: FromStr>(&self) -> Result<Vec<T>, Error> {
+        self.convert_list(None, &self.content)
+    }
+
+    /// Converts a value of the attribute key, or of the content if key is None
+    fn convert<T: FromStr>(&self, key: Option<&str>, value: &str) -> Result<T, Error> {
+        value.trim().parse().map_err(|_| {
+            Error::InvalidValue(self.tag.clone(), key.map(str::to_owned), value.to_owned())
+        })
+    }
+
+    fn convert_list<T: FromStr>(&self, key: Option<&str>, value: &str) -> Result<Vec<T>, Error> {
+        value
+            .split_whitespace()
+            .map(|v| self.convert(key, v))
+            .collect()
+    }
+}
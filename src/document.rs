@@ -0,0 +1,219 @@
+//! This is a module providing a document type which keeps everything around the root node
+//! This includes the xml declaration, the document type declaration, and top level comments and
+//! processing instructions, so that they are written back out when saving
+
+use crate::{escape, parser, tokenizer, Error, Node, ParseError, ParseOptions, WriteOptions};
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::{fmt, ops};
+
+/// A comment or processing instruction outside the root node
+#[derive(Debug, Clone, PartialEq)]
+pub enum Misc {
+    Comment(String),
+    /// A processing instruction with its target and data, such as ("xml-stylesheet", "href=\"style.css\"")
+    ProcessingInstruction(String, String),
+}
+
+/// A whole xml document
+/// The root node can be accessed through the root field, or directly as the document dereferences to it
+#[derive(Debug)]
+pub struct Document {
+    /// The version from the xml declaration
+    /// If version, encoding and standalone are all None, no declaration is written
+    pub version: Option<String>,
+    /// The encoding from the xml declaration
+    pub encoding: Option<String>,
+    /// The standalone flag from the xml declaration
+    pub standalone: Option<bool>,
+    /// The document type declaration without the surrounding <!DOCTYPE and >, such as "html"
+    pub doctype: Option<String>,
+    /// Comments and processing instructions before the root node
+    /// Comments are only kept when parsing with keep_comments
+    pub prolog: Vec<Misc>,
+    pub root: Node,
+    /// Comments and processing instructions after the root node
+    pub epilog: Vec<Misc>,
+}
+
+impl Document {
+    /// Creates a new document around a root node
+    /// The document gets an xml declaration with version 1.0
+    pub fn new(root: Node) -> Self {
+        Document {
+            version: Some("1.0".to_owned()),
+            encoding: None,
+            standalone: None,
+            doctype: None,
+            prolog: Vec::new(),
+            root,
+            epilog: Vec::new(),
+        }
+    }
+
+    /// Loads a document from a file and returns appropriate errors
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_file_with(path, ParseOptions::default())
+    }
+
+    /// Loads a document from a string and returns appropriate errors
+    pub fn from_string(string: &str) -> Result<Self, Error> {
+        Self::from_string_with(string, ParseOptions::default())
+    }
+
+    /// Loads a document from a file using the given parse options
+    pub fn from_file_with<P: AsRef<Path>>(path: P, options: ParseOptions) -> Result<Self, Error> {
+        Self::from_string_with(&std::fs::read_to_string(path)?, options)
+    }
+
+    /// Loads a document from a string using the given parse options
+    pub fn from_string_with(string: &str, options: ParseOptions) -> Result<Self, Error> {
+        parser::load(string, &options, false).map(|(document, _)| document)
+    }
+
+    /// Loads a document from a file, recovering from parse errors
+    /// Only failing to read the file is returned as an Err, see from_string_recovering
+    pub fn from_file_recovering<P: AsRef<Path>>(
+        path: P,
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<Error>), Error> {
+        Ok(Self::from_string_recovering(
+            &std::fs::read_to_string(path)?,
+            options,
+        ))
+    }
+
+    /// Loads a document from a string, recovering from parse errors instead of stopping at the first
+    /// Returns the document as far as it could be read along with every error found, ordered by position
+    pub fn from_string_recovering(string: &str, options: ParseOptions) -> (Self, Vec<Error>) {
+        parser::load(string, &options, true)
+            .unwrap_or_else(|e| (Document::new(crate::new("", String::new())), vec![e]))
+    }
+
+    /// This writes the document to a file specified by path
+    /// Uses the non-pretty to_string formatting
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save_to_file_with(path, WriteOptions::default())
+    }
+
+    /// This writes the document to a file specified by path
+    /// Uses the pretty to_string_pretty formatting
+    pub fn save_to_file_pretty<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save_to_file_with(
+            path,
+            WriteOptions {
+                pretty: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// This writes the document to a file specified by path using the given write options
+    pub fn save_to_file_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: WriteOptions,
+    ) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_string_with(options).as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Converts the document to a string with whitespace formatting
+    pub fn to_string_pretty(&self) -> String {
+        self.to_string_with(WriteOptions {
+            pretty: true,
+            ..Default::default()
+        })
+    }
+
+    /// Converts the document to a string using the given write options
+    /// Everything outside the root node is put on its own line
+    pub fn to_string_with(&self, options: WriteOptions) -> String {
+        let mut lines = Vec::new();
+
+        if self.version.is_some() || self.encoding.is_some() || self.standalone.is_some() {
+            let mut declaration = format!(
+                "<?xml version=\"{}\"",
+                self.version.as_deref().unwrap_or("1.0")
+            );
+            if let Some(encoding) = &self.encoding {
+                declaration.push_str(&format!(" encoding=\"{}\"", encoding));
+            }
+            if let Some(standalone) = self.standalone {
+                let standalone = if standalone { "yes" } else { "no" };
+                declaration.push_str(&format!(" standalone=\"{}\"", standalone));
+            }
+            declaration.push_str("?>");
+            lines.push(declaration);
+        }
+
+        if let Some(doctype) = &self.doctype {
+            lines.push(format!("<!DOCTYPE {}>", doctype));
+        }
+
+        lines.extend(self.prolog.iter().map(|misc| misc.to_string()));
+        lines.push(self.root.to_string_with(options).trim_end().to_owned());
+        lines.extend(self.epilog.iter().map(|misc| misc.to_string()));
+
+        let mut result = lines.join("\n");
+        if options.pretty {
+            result.push('\n');
+        }
+        result
+    }
+}
+
+impl fmt::Display for Misc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Misc::Comment(comment) => write!(f, "<!--{}-->", escape::escape_comment(comment)),
+            Misc::ProcessingInstruction(target, data) if data.is_empty() => {
+                write!(f, "<?{}?>", target)
+            }
+            Misc::ProcessingInstruction(target, data) => write!(f, "<?{} {}?>", target, data),
+        }
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_with(WriteOptions::default()))
+    }
+}
+
+impl ops::Deref for Document {
+    type Target = Node;
+    fn deref(&self) -> &Self::Target {
+        &self.root
+    }
+}
+
+impl ops::DerefMut for Document {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.root
+    }
+}
+
+/// Reads the version, encoding and standalone pseudo attributes of the xml declaration
+/// They are split the same way as the attributes of a start tag
+pub(crate) fn load_declaration(document: &mut Document, data: &str) -> Result<(), ParseError> {
+    for attribute in tokenizer::attributes(data) {
+        if let Some(e) = attribute.error {
+            return Err(e);
+        }
+
+        let v = attribute.value;
+        match attribute.key {
+            "version" => document.version = Some(v.to_owned()),
+            "encoding" => document.encoding = Some(v.to_owned()),
+            "standalone" if v == "yes" || v == "no" => document.standalone = Some(v == "yes"),
+            _ => return Err(ParseError::InvalidDeclaration(attribute.part.to_owned())),
+        }
+    }
+
+    Ok(())
+}
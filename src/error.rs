@@ -1,18 +1,72 @@
+use std::fmt;
+
 #[derive(Debug)]
 pub enum Error {
     IOError(std::io::Error),
-    ParseError(ParseError, usize),
+    ParseError(ParseError, Position),
+    /// Content outside the root node without a position
+    /// Parsing returns ParseError::ContentOutsideRoot instead, which is positioned
     ContentOutsideRoot,
     TagNotFound(String, String),
     AttributeNotFound(String, String),
+    /// A value which could not be converted, containing the tag of the node, the attribute key or
+    /// None for the content, and the value
+    InvalidValue(String, Option<String>, String),
+    /// An XPath expression which could not be compiled or evaluated, containing the expression and
+    /// what is wrong with it
+    InvalidXPath(String, String),
+    /// A CSS selector which could not be parsed, containing the selector and what is wrong with it
+    InvalidSelector(String, String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParseError {
     MissingClosingTag(String),
+    UnexpectedClosingTag(String),
     MissingClosingDelimiter,
     MissingAttributeValue(String),
     MissingQuotes(String),
+    /// An attribute whose closing quote is directly followed by another attribute, containing the
+    /// attribute up to that quote
+    MissingWhitespace(String),
+    MissingCommentEnd,
+    MissingCDataEnd,
+    InvalidEntity(String),
+    UnboundPrefix(String),
+    /// An invalid attribute of the xml declaration, or empty if the declaration is not at the start
+    InvalidDeclaration(String),
+    /// A '<' which is not followed by a valid tag name, containing what follows it
+    InvalidTagName(String),
+    /// Text or another node outside the root node
+    ContentOutsideRoot,
+}
+
+/// Where in the input a parse error occurred
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Position {
+    /// The line, starting at 1
+    pub line: usize,
+    /// The column in characters, starting at 1
+    pub column: usize,
+    /// The byte offset from the start of the input
+    pub offset: usize,
+    /// The path of tags to the node being parsed, such as COLLADA/library_geometries/geometry
+    /// Empty if the error occurred outside the root node
+    pub path: String,
+}
+
+impl Position {
+    /// Calculates the line and column of a byte offset into source
+    pub fn new(source: &str, offset: usize, path: &str) -> Self {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Position {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            offset,
+            path: path.to_owned(),
+        }
+    }
 }
 
 impl From<std::io::Error> for Error {
@@ -20,3 +74,199 @@ impl From<std::io::Error> for Error {
         Error::IOError(e)
     }
 }
+
+/// A stable, data-free identifier for every kind of error
+/// Useful for matching on errors or reporting them in logs without depending on their contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    IO,
+    ContentOutsideRoot,
+    TagNotFound,
+    AttributeNotFound,
+    InvalidValue,
+    MissingClosingTag,
+    UnexpectedClosingTag,
+    MissingClosingDelimiter,
+    MissingAttributeValue,
+    MissingQuotes,
+    MissingWhitespace,
+    MissingCommentEnd,
+    MissingCDataEnd,
+    InvalidEntity,
+    UnboundPrefix,
+    InvalidDeclaration,
+    InvalidTagName,
+    InvalidXPath,
+    InvalidSelector,
+}
+
+impl ErrorKind {
+    /// Returns the kind as a short kebab-case identifier, such as "missing-closing-tag"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::IO => "io",
+            ErrorKind::ContentOutsideRoot => "content-outside-root",
+            ErrorKind::TagNotFound => "tag-not-found",
+            ErrorKind::AttributeNotFound => "attribute-not-found",
+            ErrorKind::InvalidValue => "invalid-value",
+            ErrorKind::MissingClosingTag => "missing-closing-tag",
+            ErrorKind::UnexpectedClosingTag => "unexpected-closing-tag",
+            ErrorKind::MissingClosingDelimiter => "missing-closing-delimiter",
+            ErrorKind::MissingAttributeValue => "missing-attribute-value",
+            ErrorKind::MissingQuotes => "missing-quotes",
+            ErrorKind::MissingWhitespace => "missing-whitespace",
+            ErrorKind::MissingCommentEnd => "missing-comment-end",
+            ErrorKind::MissingCDataEnd => "missing-cdata-end",
+            ErrorKind::InvalidEntity => "invalid-entity",
+            ErrorKind::UnboundPrefix => "unbound-prefix",
+            ErrorKind::InvalidDeclaration => "invalid-declaration",
+            ErrorKind::InvalidTagName => "invalid-tag-name",
+            ErrorKind::InvalidXPath => "invalid-xpath",
+            ErrorKind::InvalidSelector => "invalid-selector",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Error {
+    /// Returns the kind of the error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::IOError(_) => ErrorKind::IO,
+            Error::ParseError(e, _) => e.kind(),
+            Error::ContentOutsideRoot => ErrorKind::ContentOutsideRoot,
+            Error::TagNotFound(_, _) => ErrorKind::TagNotFound,
+            Error::AttributeNotFound(_, _) => ErrorKind::AttributeNotFound,
+            Error::InvalidValue(_, _, _) => ErrorKind::InvalidValue,
+            Error::InvalidXPath(_, _) => ErrorKind::InvalidXPath,
+            Error::InvalidSelector(_, _) => ErrorKind::InvalidSelector,
+        }
+    }
+
+    /// Returns the position of a parse error, or None for other errors
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            Error::ParseError(_, position) => Some(position),
+            _ => None,
+        }
+    }
+}
+
+impl ParseError {
+    /// Returns the kind of the error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ParseError::MissingClosingTag(_) => ErrorKind::MissingClosingTag,
+            ParseError::UnexpectedClosingTag(_) => ErrorKind::UnexpectedClosingTag,
+            ParseError::MissingClosingDelimiter => ErrorKind::MissingClosingDelimiter,
+            ParseError::MissingAttributeValue(_) => ErrorKind::MissingAttributeValue,
+            ParseError::MissingQuotes(_) => ErrorKind::MissingQuotes,
+            ParseError::MissingWhitespace(_) => ErrorKind::MissingWhitespace,
+            ParseError::MissingCommentEnd => ErrorKind::MissingCommentEnd,
+            ParseError::MissingCDataEnd => ErrorKind::MissingCDataEnd,
+            ParseError::InvalidEntity(_) => ErrorKind::InvalidEntity,
+            ParseError::UnboundPrefix(_) => ErrorKind::UnboundPrefix,
+            ParseError::InvalidDeclaration(_) => ErrorKind::InvalidDeclaration,
+            ParseError::InvalidTagName(_) => ErrorKind::InvalidTagName,
+            ParseError::ContentOutsideRoot => ErrorKind::ContentOutsideRoot,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::IOError(e) => write!(f, "io error: {}", e),
+            Error::ParseError(e, position) if position.path.is_empty() => {
+                write!(f, "{}: {}", position, e)
+            }
+            Error::ParseError(e, position) => {
+                write!(f, "{}: {} (in {})", position, e, position.path)
+            }
+            Error::ContentOutsideRoot => write!(f, "content outside of the root node"),
+            Error::TagNotFound(parent, tag) => {
+                write!(f, "node <{}> has no child <{}>", parent, tag)
+            }
+            Error::AttributeNotFound(tag, key) => {
+                write!(f, "node <{}> has no attribute \"{}\"", tag, key)
+            }
+            Error::InvalidValue(tag, Some(key), value) => write!(
+                f,
+                "attribute \"{}\" of node <{}> has invalid value \"{}\"",
+                key, tag, value
+            ),
+            Error::InvalidValue(tag, None, value) => {
+                write!(f, "node <{}> has invalid content \"{}\"", tag, value)
+            }
+            Error::InvalidXPath(expression, message) => {
+                write!(f, "invalid xpath \"{}\": {}", expression, message)
+            }
+            Error::InvalidSelector(selector, message) => {
+                write!(f, "invalid selector \"{}\": {}", selector, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::IOError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingClosingTag(tag) => write!(f, "missing closing tag </{}>", tag),
+            ParseError::UnexpectedClosingTag(tag) => {
+                write!(f, "unexpected closing tag </{}>", tag)
+            }
+            ParseError::MissingClosingDelimiter => write!(f, "missing closing delimiter '>'"),
+            ParseError::MissingAttributeValue(attr) => {
+                write!(f, "attribute \"{}\" is missing a value", attr)
+            }
+            ParseError::MissingQuotes(attr) => {
+                write!(f, "attribute value is not quoted in \"{}\"", attr)
+            }
+            ParseError::MissingWhitespace(attr) => {
+                write!(f, "attribute \"{}\" is not followed by whitespace", attr)
+            }
+            ParseError::MissingCommentEnd => write!(f, "comment is missing its closing -->"),
+            ParseError::MissingCDataEnd => write!(f, "CDATA section is missing its closing ]]>"),
+            ParseError::InvalidEntity(entity) => {
+                write!(f, "unknown or malformed entity \"{}\"", entity)
+            }
+            ParseError::UnboundPrefix(prefix) => {
+                write!(f, "namespace prefix \"{}\" is not declared", prefix)
+            }
+            ParseError::InvalidDeclaration(attr) if attr.is_empty() => {
+                write!(f, "xml declaration is not at the start of the document")
+            }
+            ParseError::InvalidDeclaration(attr) => {
+                write!(f, "invalid xml declaration attribute \"{}\"", attr)
+            }
+            ParseError::InvalidTagName(name) if name.is_empty() => {
+                write!(f, "expected a tag name after '<'")
+            }
+            ParseError::InvalidTagName(name) => write!(f, "invalid tag name \"{}\"", name),
+            ParseError::ContentOutsideRoot => write!(f, "content outside of the root node"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Formats as "line 12, column 5"
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
@@ -0,0 +1,118 @@
+//! This is a module providing the functionality to decode and encode xml entities
+//! Both the predefined entities and numeric character references are supported
+
+use std::borrow::Cow;
+
+/// Replaces all entities and character references in a string with the characters they represent
+/// On failure, the unknown or malformed entity is returned, including & and ;
+pub fn unescape(string: &str) -> Result<Cow<'_, str>, String> {
+    if !string.contains('&') {
+        return Ok(Cow::Borrowed(string));
+    }
+
+    let mut result = String::with_capacity(string.len());
+    let mut rest = string;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = match rest.find(';') {
+            Some(v) => v,
+            None => return Err(rest.to_owned()),
+        };
+
+        let entity = &rest[1..end];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => char_reference(&entity[2..], 16),
+            _ if entity.starts_with('#') => char_reference(&entity[1..], 10),
+            _ => None,
+        };
+
+        match c {
+            Some(c) => result.push(c),
+            None => return Err(rest[..=end].to_owned()),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+
+    Ok(Cow::Owned(result))
+}
+
+/// Returns the character of a numeric character reference, given its digits in radix
+/// Only digits are allowed, and the character has to be one that may appear in an xml document
+fn char_reference(digits: &str, radix: u32) -> Option<char> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+
+    let c = std::char::from_u32(u32::from_str_radix(digits, radix).ok()?)?;
+    match c {
+        '\t'
+        | '\n'
+        | '\r'
+        | '\u{20}'..='\u{d7ff}'
+        | '\u{e000}'..='\u{fffd}'
+        | '\u{10000}'..='\u{10ffff}' => Some(c),
+        _ => None,
+    }
+}
+
+/// Escapes the characters that can not appear literally in text content
+pub fn escape_text(string: &str) -> Cow<'_, str> {
+    escape(string, &['&', '<', '>'])
+}
+
+/// Escapes the characters that can not appear literally in a double quoted attribute value
+/// Newlines, carriage returns and tabs are escaped too, since parsers turn them into spaces
+pub fn escape_attribute(string: &str) -> Cow<'_, str> {
+    escape(string, &['&', '<', '>', '"', '\n', '\r', '\t'])
+}
+
+/// Separates the hyphens that can not appear in a comment, "--" and a "-" before the closing -->
+/// A space is put between them, so "a--b-" is written as "a- -b- "
+pub fn escape_comment(string: &str) -> Cow<'_, str> {
+    if !string.contains("--") && !string.ends_with('-') {
+        return Cow::Borrowed(string);
+    }
+
+    let mut result = String::with_capacity(string.len() + 4);
+    for c in string.chars() {
+        if c == '-' && result.ends_with('-') {
+            result.push(' ');
+        }
+        result.push(c);
+    }
+    if result.ends_with('-') {
+        result.push(' ');
+    }
+
+    Cow::Owned(result)
+}
+
+fn escape<'a>(string: &'a str, special: &[char]) -> Cow<'a, str> {
+    if !string.contains(special) {
+        return Cow::Borrowed(string);
+    }
+
+    let mut result = String::with_capacity(string.len() + 16);
+    for c in string.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' if special.contains(&'"') => result.push_str("&quot;"),
+            '\n' if special.contains(&'\n') => result.push_str("&#10;"),
+            '\r' if special.contains(&'\r') => result.push_str("&#13;"),
+            '\t' if special.contains(&'\t') => result.push_str("&#9;"),
+            c => result.push(c),
+        }
+    }
+
+    Cow::Owned(result)
+}
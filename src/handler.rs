@@ -0,0 +1,91 @@
+//! This is a module providing callback based parsing, where a handler is called for every part of the xml
+//! It is driven by the pull reader, so input is read incrementally and no tree is built
+//! ```
+//! use szl_simple_xml::{Attributes, Handler};
+//!
+//! struct Count(usize);
+//!
+//! impl Handler for Count {
+//!     fn start_element(&mut self, _name: &str, _attributes: &Attributes) {
+//!         self.0 += 1;
+//!     }
+//! }
+//!
+//! let mut count = Count(0);
+//! szl_simple_xml::parse_with_handler("<a><b/><b/></a>".as_bytes(), &mut count).unwrap();
+//! assert_eq!(count.0, 3);
+//! ```
+
+use crate::reader::{Event, Reader};
+use crate::{Attributes, Error};
+use std::io::Read;
+
+/// Callbacks for the parts of an xml document, all of which do nothing by default
+pub trait Handler {
+    /// Called for an opening tag, or an empty tag which is followed by end_element
+    fn start_element(&mut self, _name: &str, _attributes: &Attributes) {}
+
+    fn end_element(&mut self, _name: &str) {}
+
+    /// Called for a run of text with entities decoded and surrounding whitespace removed
+    fn characters(&mut self, _text: &str) {}
+
+    /// Called for a CDATA section, which is passed on to characters unless overridden
+    fn cdata(&mut self, data: &str) {
+        self.characters(data)
+    }
+
+    /// Called for a comment, only when parsing with keep_comments
+    fn comment(&mut self, _comment: &str) {}
+
+    /// Called for a processing instruction with its target and data, including the xml declaration
+    fn processing_instruction(&mut self, _target: &str, _data: &str) {}
+
+    /// Called for the document type declaration without the surrounding <!DOCTYPE and >
+    fn doctype(&mut self, _doctype: &str) {}
+
+    /// Called with the error which stopped parsing, before it is returned
+    fn error(&mut self, _error: &Error) {}
+
+    /// Called once the whole input has been parsed without errors
+    fn end_document(&mut self) {}
+}
+
+/// Parses xml from an io::Read with the default parse options, calling handler for everything read
+/// To use other options, see Reader::handle
+pub fn parse_with_handler<R: Read, H: Handler>(reader: R, handler: &mut H) -> Result<(), Error> {
+    Reader::new(reader).handle(handler)
+}
+
+impl<R: Read> Reader<R> {
+    /// Reads the remaining events, calling handler for each of them
+    pub fn handle<H: Handler>(&mut self, handler: &mut H) -> Result<(), Error> {
+        loop {
+            let event = match self.next_event() {
+                Ok(v) => v,
+                Err(e) => {
+                    handler.error(&e);
+                    return Err(e);
+                }
+            };
+
+            match event {
+                Event::StartElement { name, attributes } => {
+                    handler.start_element(&name, &attributes)
+                }
+                Event::EndElement { name } => handler.end_element(&name),
+                Event::Text(text) => handler.characters(&text),
+                Event::CData(data) => handler.cdata(&data),
+                Event::Comment(comment) => handler.comment(&comment),
+                Event::ProcessingInstruction(target, data) => {
+                    handler.processing_instruction(&target, &data)
+                }
+                Event::Doctype(doctype) => handler.doctype(&doctype),
+                Event::Eof => {
+                    handler.end_document();
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
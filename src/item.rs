@@ -0,0 +1,184 @@
+//! This is a module providing access to the children of a node in document order
+//! Child nodes are still stored by tag so that indexing by tag keeps working, the order is kept
+//! alongside as a list of entries referring to the nodes by key, so removing a node leaves the
+//! others where they were
+
+use crate::Node;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// An entry in the document order of a node
+#[derive(Debug)]
+pub(crate) enum Entry {
+    /// A child node, referred to by tag and key
+    Node(String, usize),
+    Text(String),
+    CData(String),
+    Comment(String),
+}
+
+/// Returns a key which no other node has
+pub(crate) fn next_key() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Returns the index of the node with the given key
+/// Nodes are usually added in the order they are created, so the keys are tried as sorted first
+fn find(nodes: &[Node], key: usize) -> Option<usize> {
+    match nodes.binary_search_by_key(&key, |node| node.key) {
+        Ok(i) => Some(i),
+        Err(_) => nodes.iter().position(|node| node.key == key),
+    }
+}
+
+/// A child of a node as returned by Node::items
+#[derive(Debug, Clone, Copy)]
+pub enum Item<'a> {
+    Node(&'a Node),
+    /// A run of text with entities decoded
+    /// Whitespace at the start and end of the node is removed, and between children unless the
+    /// node also has text, so that the spaces between words in mixed content are kept
+    Text(&'a str),
+    CData(&'a str),
+    Comment(&'a str),
+}
+
+impl Node {
+    /// Returns the child nodes, text, CDATA sections and comments in document order
+    /// This is also the order in which they are written
+    /// Nodes added through get_mut_nodes come after the ordered items
+    /// If content was changed after parsing, it is returned as a single text item at the end
+    /// instead of the original text and CDATA items
+    pub fn items(&self) -> impl Iterator<Item = Item<'_>> {
+        let mut items = Vec::with_capacity(self.order.len());
+        let mut text = String::new();
+        let mut found: usize = 0;
+
+        for entry in &self.order {
+            match entry {
+                Entry::Node(tag, key) => {
+                    let nodes = self.nodes.get(tag).map_or(&[][..], |nodes| &nodes[..]);
+                    if let Some(i) = find(nodes, *key) {
+                        found += 1;
+                        items.push(Item::Node(&nodes[i]));
+                    }
+                }
+                Entry::Text(v) => {
+                    text.push_str(v);
+                    items.push(Item::Text(v));
+                }
+                Entry::CData(v) => {
+                    text.push_str(v);
+                    items.push(Item::CData(v));
+                }
+                Entry::Comment(v) => items.push(Item::Comment(v)),
+            }
+        }
+
+        // Sort by tag so the output does not depend on hash order
+        if found < self.nodes.values().map(Vec::len).sum() {
+            let ordered: HashSet<_> = items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Node(node) => Some(node.key),
+                    _ => None,
+                })
+                .collect();
+            let mut unordered: Vec<_> = self
+                .nodes
+                .iter()
+                .flat_map(|(tag, nodes)| nodes.iter().enumerate().map(move |(i, n)| (tag, i, n)))
+                .filter(|(_, _, node)| !ordered.contains(&node.key))
+                .collect();
+            unordered.sort_by_key(|(tag, i, _)| (tag.as_str(), *i));
+            items.extend(unordered.into_iter().map(|(_, _, node)| Item::Node(node)));
+        }
+
+        if text != self.content {
+            items.retain(|item| !matches!(item, Item::Text(_) | Item::CData(_)));
+            if !self.content.is_empty() {
+                items.push(Item::Text(&self.content));
+            }
+        }
+
+        items.into_iter()
+    }
+
+    /// Returns the child nodes in document order, without text and comments
+    pub fn children(&self) -> impl Iterator<Item = &Node> {
+        self.items().filter_map(|item| match item {
+            Item::Node(node) => Some(node),
+            _ => None,
+        })
+    }
+
+    /// Returns the child nodes mutably in document order
+    pub fn children_mut(&mut self) -> impl Iterator<Item = &mut Node> {
+        let nodes = &self.nodes;
+        let ordered: Vec<_> = self
+            .order
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Node(tag, key) => Some((tag.as_str(), find(nodes.get(tag)?, *key)?)),
+                _ => None,
+            })
+            .collect();
+
+        let mut slots: HashMap<&str, Vec<Option<&mut Node>>> = self
+            .nodes
+            .iter_mut()
+            .map(|(tag, nodes)| (tag.as_str(), nodes.iter_mut().map(Some).collect()))
+            .collect();
+
+        let mut children = Vec::with_capacity(ordered.len());
+        for (tag, i) in ordered {
+            let slot = slots.get_mut(tag).and_then(|nodes| nodes.get_mut(i));
+            if let Some(node) = slot.and_then(Option::take) {
+                children.push(node);
+            }
+        }
+
+        // The same order as items, so unordered nodes come last sorted by tag
+        let mut unordered: Vec<_> = slots
+            .into_iter()
+            .flat_map(|(tag, nodes)| {
+                nodes
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(move |(i, node)| Some((tag, i, node?)))
+            })
+            .collect();
+        unordered.sort_by_key(|(tag, i, _)| (*tag, *i));
+        children.extend(unordered.into_iter().map(|(_, _, node)| node));
+        children.into_iter()
+    }
+
+    /// Returns the comments directly inside this node
+    /// Comments are only kept when parsing with keep_comments
+    pub fn comments(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().filter_map(|entry| match entry {
+            Entry::Comment(v) => Some(v.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Appends a run of text after the current children
+    /// The text is also added to content
+    pub fn add_text(&mut self, text: &str) {
+        self.content.push_str(text);
+        self.order.push(Entry::Text(text.to_owned()));
+    }
+
+    /// Appends a CDATA section after the current children
+    /// The data is also added to content
+    pub fn add_cdata(&mut self, data: &str) {
+        self.content.push_str(data);
+        self.order.push(Entry::CData(data.to_owned()));
+    }
+
+    /// Appends a comment after the current children
+    pub fn add_comment(&mut self, comment: &str) {
+        self.order.push(Entry::Comment(comment.to_owned()));
+    }
+}
@@ -1,10 +1,12 @@
 //! XML parser and writer
 //! This crate can load xml from a file or string and parse it into memory
 //! XML can also be manipulated or created and the written to file
+//! Trees are parsed, walked, written, copied and dropped without recursion, so deeply nested xml
+//! can not overflow the call stack
 //! ## Loading xml from a file
 //! ```
-//! fn load_message() -> Result<(), simple_xml::Error> {
-//!     let root = simple_xml::from_file("examples/message.xml")?;
+//! fn load_message() -> Result<(), szl_simple_xml::Error> {
+//!     let root = szl_simple_xml::from_file("examples/message.xml")?;
 //!     // Since there can multiple nodes/tags with the same name, we need to index twice
 //!     let heading = &root["heading"][0];
 //!     println!("Heading: {}", heading.content);
@@ -19,7 +21,7 @@
 //! let name = String::from("Tim Roberts");
 //! let health = 50;
 //!
-//! let mut player = simple_xml::new("player", String::new());
+//! let mut player = szl_simple_xml::new("player", String::new());
 //! player.add_new_node("health", health.to_string());
 //! player.add_new_node("name", name);
 //! // Save to file
@@ -27,21 +29,40 @@
 //! ```
 //! ## Editing xml structures
 //! ```
-//!let file =     
-//!    szl_simple_xml::from_file("./examples/note.xml").expect("Failed to parse simple_xml");
-//!    let mut resources = 
-//!        &mut file.get_mut_nodes("resources").unwrap()[0].get_mut_nodes("resource").unwrap()[0];
-//!    
-//!    let href = String::from("page1.html");
-//!    let new_file_node = szl_simple_xml::new("file", String::new());
-//!    new_file_node.add_attribute("href", &href);
+//! let mut file =
+//!     szl_simple_xml::from_file("./examples/mutable.xml").expect("Failed to parse simple_xml");
+//! let resources =
+//!     &mut file.get_mut_nodes("resources").unwrap()[0].get_mut_nodes("resource").unwrap()[0];
 //!
-//!    resources.add_node(new_file_node);
-//!    let write_file = file.save_to_file_pretty("./test.xml")
+//! let href = String::from("page1.html");
+//! let mut new_file_node = szl_simple_xml::new("file", String::new());
+//! new_file_node.add_attribute("href", &href);
+//!
+//! resources.add_node(new_file_node);
+//! let _ = file.to_string_pretty();
+//! ```
+//! ## Comments
+//! Comments are skipped by default. They can be kept in the tree, and written back out, by
+//! parsing with [`ParseOptions`]
+//! ```
+//! let options = szl_simple_xml::ParseOptions {
+//!     keep_comments: true,
+//!     ..Default::default()
+//! };
+//! let root = szl_simple_xml::from_string_with("<a><!-- note --><b/></a>", options).unwrap();
+//! assert_eq!(root.comments().collect::<Vec<_>>(), vec![" note "]);
+//! assert_eq!(root.to_string(), "<a><!-- note --><b/></a>");
+//! ```
+//! ## Keeping the xml declaration
+//! from_file only returns the root node. To keep the declaration, DOCTYPE and anything else
+//! around the root, load a [`Document`] instead
+//! ```
+//! let mut document = szl_simple_xml::Document::from_file("./examples/mutable.xml").unwrap();
+//! document.root.add_new_node("version", "2".to_owned());
+//! assert!(document.to_string().starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>"));
 //! ```
 //! For more example, see the tests
 
-
 use std::collections::HashMap;
 use std::fs::File;
 use std::io;
@@ -50,47 +71,142 @@ use std::path::Path;
 use std::{fmt, ops};
 
 mod split_unquoted;
-use split_unquoted::SplitUnquoted;
 
 pub mod error;
 pub use error::Error;
+pub use error::ErrorKind;
 pub use error::ParseError;
+pub use error::Position;
+
+pub mod arena;
+
+mod attributes;
+pub use attributes::Attributes;
+
+mod borrowed;
+pub use borrowed::{BorrowedItem, BorrowedNode};
+
+mod check;
+
+mod convert;
+
+#[cfg(feature = "tokio")]
+pub mod async_io;
+#[cfg(feature = "tokio")]
+pub use async_io::{from_async_reader, from_async_reader_with};
+
+pub mod escape;
+
+mod document;
+pub use document::{Document, Misc};
+
+mod handler;
+pub use handler::{parse_with_handler, Handler};
+
+mod item;
+use item::Entry;
+pub use item::Item;
+
+pub mod namespace;
+
+mod parser;
+pub mod push;
+pub mod reader;
+mod tokenizer;
+
+pub mod report;
+use namespace::Scope;
+
+pub mod select;
+
+mod walk;
+pub use walk::{BreadthFirst, Descendants};
+
+mod write;
+pub use write::WriteOptions;
+
+pub mod xpath;
 
-#[derive(Debug)]
 pub struct Node {
     pub tag: String,
-    pub attributes: HashMap<String, String>,
-    nodes: HashMap<String, Vec<Node>>,
+    pub attributes: Attributes,
+    nodes: Children,
     pub content: String,
+    /// Identifies the node in the order of its parent
+    key: usize,
+    /// The child nodes, text and comments in document order
+    order: Vec<Entry>,
+    /// The namespace declarations in scope, only set when parsing with namespaces
+    namespaces: Option<Scope>,
 }
 
-struct Payload<'a> {
-    prolog: &'a str,
-    node: Option<Node>,
-    remaining: &'a str,
-}
-
-fn validate_root(root: Result<Payload, Error>) -> Result<Node, Error> {
-    match root {
-        Ok(v) if !v.prolog.is_empty() => Err(Error::ContentOutsideRoot),
-        Ok(v) => Ok(v.node.unwrap_or(Node {
-            tag: String::new(),
-            content: String::new(),
-            nodes: HashMap::new(),
-            attributes: HashMap::new(),
-        })),
-        Err(e) => Err(e),
-    }
+/// Options controlling how xml is parsed
+/// The default options match the behaviour of from_file and from_string
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParseOptions {
+    /// Keep comments in the tree so they are written back out when saving
+    pub keep_comments: bool,
+    /// Keep entities and character references such as &amp;amp; as they appear in the source
+    /// instead of decoding them
+    pub raw: bool,
+    /// Resolve namespace prefixes so that nodes expose their namespace uri
+    /// Using an undeclared prefix is then an error
+    pub namespaces: bool,
 }
 
 /// Loads an xml structure from a file and returns appropriate errors
 pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Node, Error> {
-    validate_root(load_from_slice(&std::fs::read_to_string(path)?))
+    from_file_with(path, ParseOptions::default())
 }
 
 /// Loads an xml structure from a string and returns appropriate errors
 pub fn from_string(string: &str) -> Result<Node, Error> {
-    validate_root(load_from_slice(string))
+    from_string_with(string, ParseOptions::default())
+}
+
+/// Loads an xml structure from a file using the given parse options
+pub fn from_file_with<P: AsRef<Path>>(path: P, options: ParseOptions) -> Result<Node, Error> {
+    from_string_with(&std::fs::read_to_string(path)?, options)
+}
+
+/// Loads an xml structure from a string using the given parse options
+pub fn from_string_with(string: &str, options: ParseOptions) -> Result<Node, Error> {
+    parser::load(string, &options, false).map(|(doc, _)| doc.root)
+}
+
+/// Loads an xml structure from a string without copying it, see BorrowedNode
+pub fn from_string_borrowed(string: &str) -> Result<BorrowedNode<'_>, Error> {
+    from_string_borrowed_with(string, ParseOptions::default())
+}
+
+/// Loads an xml structure from a string without copying it, using the given parse options
+pub fn from_string_borrowed_with(
+    string: &str,
+    options: ParseOptions,
+) -> Result<BorrowedNode<'_>, Error> {
+    parser::build(string, &options, false).map(|(_, root, _)| root)
+}
+
+/// Loads an xml structure from a file, recovering from parse errors
+/// Only failing to read the file is returned as an Err, see from_string_recovering
+pub fn from_file_recovering<P: AsRef<Path>>(
+    path: P,
+    options: ParseOptions,
+) -> Result<(Node, Vec<Error>), Error> {
+    Ok(from_string_recovering(
+        &std::fs::read_to_string(path)?,
+        options,
+    ))
+}
+
+/// Loads an xml structure from a string, recovering from parse errors instead of stopping at the first
+/// Returns the nodes that could be read along with every error found, ordered by position
+/// Unclosed nodes end where their parent ends, stray '<' are kept as text, and malformed attributes
+/// and misplaced closing tags are skipped
+/// Nodes left unclosed together are reported once, at the innermost of them
+pub fn from_string_recovering(string: &str, options: ParseOptions) -> (Node, Vec<Error>) {
+    let (document, errors) = Document::from_string_recovering(string, options);
+    (document.root, errors)
 }
 
 /// Creates a new empty node
@@ -99,180 +215,55 @@ pub fn from_string(string: &str) -> Result<Node, Error> {
 /// Tag is not taken owned as it is most often a string literal
 pub fn new(tag: &str, content: String) -> Node {
     Node {
-        attributes: HashMap::new(),
+        attributes: Attributes::new(),
         content,
         tag: tag.to_owned(),
-        nodes: HashMap::new(),
+        nodes: Children::default(),
+        key: item::next_key(),
+        order: Vec::new(),
+        namespaces: None,
     }
 }
 
 /// Creates a new node with given tag, attributes content, and child nodes
-pub fn new_filled(
+/// Attributes can be given as a HashMap, in which case they are sorted by key, or as Attributes
+pub fn new_filled<A: Into<Attributes>>(
     tag: &str,
-    attributes: HashMap<String, String>,
+    attributes: A,
     content: String,
     nodes: HashMap<String, Vec<Node>>,
 ) -> Node {
+    // Sort by tag so the order does not depend on hash order
+    let mut tags: Vec<_> = nodes.iter().collect();
+    tags.sort_by_key(|(tag, _)| tag.as_str());
+    let order = tags
+        .into_iter()
+        .flat_map(|(tag, nodes)| nodes.iter().map(move |n| Entry::Node(tag.clone(), n.key)))
+        .collect();
+
     Node {
         tag: tag.to_owned(),
-        attributes,
-        nodes,
+        attributes: attributes.into(),
+        nodes: Children(nodes),
         content,
+        key: item::next_key(),
+        order,
+        namespaces: None,
     }
 }
 
-/// Calculates the number of newlines '\n' in a slice
-fn newlines_in_slice(string: &str) -> usize {
-    string.chars().filter(|c| *c == '\n').count()
-}
-
-/// Loads a xml structure from a slice
-/// Ok variant contains a payload with the child node, name prolog, and remaining stringtuple with (prolog, tag_name, tag_data, remaining_from_in)
-fn load_from_slice(string: &str) -> Result<Payload, Error> {
-    let opening_del = match string.find('<') {
-        Some(v) => v,
-        None => {
-            return Ok(Payload {
-                prolog: "",
-                node: None,
-                remaining: string,
-            });
-        }
-    };
-
-    let closing_del = match string.find('>') {
-        Some(v) => v,
-        None => {
-            return Err(Error::ParseError(
-                ParseError::MissingClosingDelimiter,
-                newlines_in_slice(&string[..opening_del]),
-            ))
-        }
-    };
-
-    // Do not consider / of empty as a part
-    let attr_end = if &string[closing_del - 1..closing_del] == "/" {
-        closing_del - 1
-    } else {
-        closing_del
-    };
-
-    let mut tag_parts =
-        SplitUnquoted::split(&string[opening_del + 1..attr_end], |c| c.is_whitespace());
-
-    let tag_name = tag_parts.next().unwrap().trim();
-
-    // Collect the prolog as everything before opening tag excluding whitespace
-    let prolog = string[..opening_del].trim();
-
-    // Is a comment
-    // Attempt to read past comment
-    if &tag_name[0..1] == "?" {
-        return load_from_slice(&string[closing_del + 1..]);
-    }
-
-    let mut attributes = HashMap::new();
-    for part in tag_parts {
-        let equal_sign = match part.find('=') {
-            Some(v) => v,
-            None => {
-                return Err(Error::ParseError(
-                    ParseError::MissingAttributeValue(part.to_owned()),
-                    newlines_in_slice(&string[..closing_del]),
-                ))
-            }
-        };
-
-        // Get key and value from attribute
-        let (k, v) = part.split_at(equal_sign);
-
-        // Remove quotes from value
-        let v = if &v[1..2] == "\"" && (&v[v.len() - 1..] == "\"" || v.ends_with("\"/")) {
-            &v[2..v.len() - 1]
-        } else {
-            return Err(Error::ParseError(
-                ParseError::MissingQuotes(part.to_owned()),
-                newlines_in_slice(&string[..closing_del]),
-            ));
-        };
-        attributes.insert(k.to_owned(), v.to_owned());
-    }
-
-    // Empty but valid node
-    if string[opening_del + 1..closing_del].ends_with('/') {
-        return Ok(Payload {
-            prolog,
-            node: Some(Node {
-                tag: tag_name.to_owned(),
-                nodes: HashMap::new(),
-                attributes,
-                content: String::new(),
-            }),
-            remaining: &string[closing_del + 1..],
-        });
-    }
-
-    // Find the closing tag index
-    let closing_tag = match string.find(&format!("</{}>", tag_name)) {
-        Some(v) => v,
-        None => {
-            return Err(Error::ParseError(
-                ParseError::MissingClosingTag(tag_name.to_owned()),
-                newlines_in_slice(&string[..closing_del]),
-            ))
-        }
-    };
-
-    let mut content = String::with_capacity(512);
-    let mut nodes = HashMap::new();
-
-    // Load the inside contents and nodes
-    let mut buf = &string[closing_del + 1..closing_tag];
-    let mut offset = closing_del;
-    while !buf.is_empty() {
-        let payload = load_from_slice(buf).map_err(|e| match e {
-            Error::ParseError(e, ln) => {
-                Error::ParseError(e, ln + newlines_in_slice(&string[..offset]))
-            }
-            e => e,
-        })?;
-
-        if let Some(node) = payload.node {
-            let v: &mut Vec<_> = nodes.entry(node.tag.clone()).or_default();
-            v.push(node);
-        }
-
-        // Nothing was read by node, no more nodes
-        if payload.remaining.as_ptr() == buf.as_ptr() {
-            break;
-        }
-
-        // Put what was before the next tag into the content of the parent tag
-        content.push_str(payload.prolog);
-        offset += buf.len() - payload.remaining.len();
-        buf = payload.remaining;
-    }
-
-    // Add the remaining inside content to content after no more nodes where found
-    content.push_str(buf);
-
-    let remaining = &string[closing_tag + tag_name.len() + 3..];
-
-    Ok(Payload {
-        prolog,
-        node: Some(Node {
-            tag: tag_name.to_owned(),
-            attributes,
-            nodes,
-            content: content.trim().into(),
-        }),
-        remaining,
-    })
+/// Splits a step of a path such as file[2] into the tag and index
+/// A step without a valid index is taken as a tag with index 0
+fn path_step(step: &str) -> (&str, usize) {
+    let indexed = step
+        .strip_suffix(']')
+        .and_then(|rest| rest.split_once('['))
+        .and_then(|(tag, i)| Some((tag, i.parse().ok()?)));
+    indexed.unwrap_or((step, 0))
 }
 
 impl Node {
-
-    /// Returns a mutable list of nodes 
+    /// Returns a mutable list of nodes
     /// If no nodes with the specified tag exists, None is returned
     pub fn get_mut_nodes(&mut self, tag: &str) -> Option<&mut Vec<Node>> {
         self.nodes.get_mut(tag)
@@ -294,6 +285,48 @@ impl Node {
         }
     }
 
+    /// Returns the node at a slash separated path of tags, such as "resources/resource/file[2]"
+    /// An index selects among the nodes with that tag, starting at 0, and defaults to 0
+    /// If any node along the path doesn't exist, None is returned
+    pub fn at(&self, path: &str) -> Option<&Node> {
+        let mut node = self;
+        for step in path.split('/').filter(|step| !step.is_empty()) {
+            let (tag, i) = path_step(step);
+            node = node.nodes.get(tag)?.get(i)?;
+        }
+        Some(node)
+    }
+
+    /// Returns the node at a slash separated path of tags mutably
+    /// Works exactly like at
+    pub fn at_mut(&mut self, path: &str) -> Option<&mut Node> {
+        let mut node = self;
+        for step in path.split('/').filter(|step| !step.is_empty()) {
+            let (tag, i) = path_step(step);
+            node = node.nodes.get_mut(tag)?.get_mut(i)?;
+        }
+        Some(node)
+    }
+
+    /// Returns the node at a slash separated path of tags
+    /// If any node along the path doesn't exist, an Err of TagNotFound is returned containing the
+    /// path to the last node found and the step which failed
+    /// Otherwise, works exactly like at but can be chained with ? (try operator)
+    pub fn try_at(&self, path: &str) -> Result<&Node, Error> {
+        let mut node = self;
+        let mut found = self.tag.clone();
+        for step in path.split('/').filter(|step| !step.is_empty()) {
+            let (tag, i) = path_step(step);
+            node = match node.nodes.get(tag).and_then(|nodes| nodes.get(i)) {
+                Some(v) => v,
+                None => return Err(Error::TagNotFound(found, step.to_owned())),
+            };
+            found.push('/');
+            found.push_str(step);
+        }
+        Ok(node)
+    }
+
     /// Adds or updates an attribute
     /// If an attribute with that key already exists it is returned
     pub fn add_attribute(&mut self, key: &str, val: &str) -> Option<String> {
@@ -318,9 +351,10 @@ impl Node {
     }
 
     /// Inserts a new node node with the name of the node field
+    /// The node is placed after all current children
     pub fn add_node(&mut self, node: Node) {
-        let v = self.nodes.entry(node.tag.clone()).or_default();
-        v.push(node);
+        self.order.push(Entry::Node(node.tag.clone(), node.key));
+        self.nodes.entry(node.tag.clone()).or_default().push(node);
     }
 
     /// Inserts a new node into the xml structure
@@ -332,103 +366,52 @@ impl Node {
     /// This writes an xml structure to a file specified by path
     /// Uses the non-pretty to_string formatting
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
-        let mut file = File::create(path)?;
-        file.write_all(self.to_string().as_bytes())?;
-
-        Ok(())
+        self.save_to_file_with(path, WriteOptions::default())
     }
 
     /// This writes an xml structure to a file specified by path
     /// Uses the pretty to_string_pretty formatting
     pub fn save_to_file_pretty<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save_to_file_with(
+            path,
+            WriteOptions {
+                pretty: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// This writes an xml structure to a file specified by path using the given write options
+    pub fn save_to_file_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: WriteOptions,
+    ) -> io::Result<()> {
         let mut file = File::create(path)?;
-        file.write_all(self.to_string_pretty().as_bytes())?;
+        file.write_all(self.to_string_with(options).as_bytes())?;
 
         Ok(())
     }
 
     // Converts an xml structure to a string with whitespace formatting
     pub fn to_string_pretty(&self) -> String {
-        fn internal(node: &Node, depth: usize) -> String {
-            if node.tag.is_empty() {
-                return "".to_owned();
-            }
-
-            match node.nodes.len() + node.content.len() {
-                0 => format!(
-                    "{indent}<{}{}/>\n",
-                    node.tag,
-                    node.attributes
-                        .iter()
-                        .map(|(k, v)| format!(" {}=\"{}\"", k, v))
-                        .collect::<String>(),
-                    indent = " ".repeat(depth * 4)
-                ),
-                _ => format!(
-                    "{indent}<{tag}{attr}>{beg}{nodes}{content}{end}</{tag}>\n",
-                    tag = node.tag,
-                    attr = node
-                        .attributes
-                        .iter()
-                        .map(|(k, v)| format!(" {}=\"{}\"", k, v))
-                        .collect::<String>(),
-                    nodes = node
-                        .nodes
-                        .iter()
-                        .flat_map(|(_, nodes)| nodes.iter())
-                        .map(|node| internal(node, depth + 1))
-                        .collect::<String>(),
-                    beg = match node.nodes.len() {
-                        0 => "",
-                        _ => "\n",
-                    },
-                    end = match node.nodes.len() {
-                        0 => "".to_owned(),
-                        _ => " ".repeat(depth * 4),
-                    },
-                    content = node.content,
-                    indent = " ".repeat(depth * 4),
-                ),
-            }
-        }
-        internal(self, 0)
+        self.to_string_with(WriteOptions {
+            pretty: true,
+            ..Default::default()
+        })
+    }
+
+    /// Converts an xml structure to a string using the given write options
+    pub fn to_string_with(&self, options: WriteOptions) -> String {
+        let mut out = String::new();
+        write::write_node(self, &options, 0, &mut out);
+        out
     }
 }
 
 impl std::fmt::Display for Node {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
-        if self.tag.is_empty() {
-            return write!(f, "");
-        }
-
-        match self.nodes.len() + self.content.len() {
-            0 => write!(
-                f,
-                "<{}{}/>",
-                self.tag,
-                self.attributes
-                    .iter()
-                    .map(|(k, v)| format!(" {}=\"{}\"", k, v))
-                    .collect::<String>(),
-            ),
-            _ => write!(
-                f,
-                "<{tag}{attr}>{nodes}{content}</{tag}>",
-                tag = self.tag,
-                attr = self
-                    .attributes
-                    .iter()
-                    .map(|(k, v)| format!(" {}=\"{}\"", k, v))
-                    .collect::<String>(),
-                nodes = self
-                    .nodes
-                    .iter()
-                    .flat_map(|(_, nodes)| nodes.iter())
-                    .map(|node| node.to_string())
-                    .collect::<String>(),
-                content = self.content,
-            ),
-        }
+        f.write_str(&self.to_string_with(WriteOptions::default()))
     }
 }
 
@@ -443,3 +426,45 @@ impl ops::Index<&str> for Node {
         }
     }
 }
+
+/// Child nodes are listed by tag only
+/// The whole tree is written by to_string
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let children: Vec<_> = self.children().map(|node| &node.tag).collect();
+        f.debug_struct("Node")
+            .field("tag", &self.tag)
+            .field("attributes", &self.attributes)
+            .field("content", &self.content)
+            .field("children", &children)
+            .finish()
+    }
+}
+
+/// The child nodes of a node grouped by tag
+#[derive(Default)]
+struct Children(HashMap<String, Vec<Node>>);
+
+impl ops::Deref for Children {
+    type Target = HashMap<String, Vec<Node>>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl ops::DerefMut for Children {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Drops the child nodes one at a time instead of recursively
+/// Node itself does not implement Drop, so its fields can still be moved out
+impl Drop for Children {
+    fn drop(&mut self) {
+        let mut stack: Vec<Node> = self.0.drain().flat_map(|(_, v)| v).collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.nodes.0.drain().flat_map(|(_, v)| v));
+        }
+    }
+}
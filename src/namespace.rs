@@ -0,0 +1,138 @@
+//! This is a module providing namespace resolution for nodes parsed with the namespaces option
+//! Every node keeps the namespace declarations in scope, shared with its parent unless it declares new ones
+
+use crate::{Error, Node, ParseError};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The namespace bound to the xml prefix in every document
+pub const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// The namespace declarations in scope, mapping prefix to uri
+/// The default namespace uses the empty prefix
+pub(crate) type Scope = Arc<HashMap<String, String>>;
+
+/// Returns the scope of a node with the given attribute keys and values inside parent
+pub(crate) fn resolve_scope<'b>(
+    attributes: impl IntoIterator<Item = (&'b str, &'b str)>,
+    parent: &Scope,
+) -> Scope {
+    let declarations: Vec<_> = attributes
+        .into_iter()
+        .filter_map(|(k, v)| match k {
+            "xmlns" => Some(("", v)),
+            _ => k.strip_prefix("xmlns:").map(|prefix| (prefix, v)),
+        })
+        .collect();
+
+    match declarations.len() {
+        0 => parent.clone(),
+        _ => {
+            let mut scope = HashMap::clone(parent);
+            for (prefix, uri) in declarations {
+                scope.insert(prefix.to_owned(), uri.to_owned());
+            }
+            Arc::new(scope)
+        }
+    }
+}
+
+/// Fails if the tag or any of the attributes of a node use a prefix which is not declared in scope
+pub(crate) fn check_prefixes<'b>(
+    tag: &'b str,
+    keys: impl IntoIterator<Item = &'b str>,
+    scope: &Scope,
+) -> Result<(), ParseError> {
+    let names = std::iter::once(tag).chain(
+        keys.into_iter()
+            .filter(|k| *k != "xmlns" && !k.starts_with("xmlns:")),
+    );
+    for name in names {
+        if let Some((prefix, _)) = name.split_once(':') {
+            if prefix != "xml" && !scope.contains_key(prefix) {
+                return Err(ParseError::UnboundPrefix(prefix.to_owned()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Node {
+    /// Returns the prefix of the tag, the part before the colon
+    /// If the tag has no prefix, None is returned
+    pub fn prefix(&self) -> Option<&str> {
+        self.tag.split_once(':').map(|(prefix, _)| prefix)
+    }
+
+    /// Returns the tag without its prefix
+    pub fn local_name(&self) -> &str {
+        match self.tag.split_once(':') {
+            Some((_, name)) => name,
+            None => &self.tag,
+        }
+    }
+
+    /// Returns the namespace uri of the node
+    /// Namespaces are only resolved when parsing with the namespaces option, otherwise None is returned
+    pub fn namespace(&self) -> Option<&str> {
+        self.lookup_namespace(self.prefix().unwrap_or(""))
+    }
+
+    /// Returns the namespace uri bound to a prefix in the scope of this node
+    /// The default namespace is looked up with an empty prefix
+    pub fn lookup_namespace(&self, prefix: &str) -> Option<&str> {
+        let scope = self.namespaces.as_ref()?;
+        match prefix {
+            "xml" => Some(XML_NAMESPACE),
+            _ => scope
+                .get(prefix)
+                .map(|uri| uri.as_str())
+                .filter(|uri| !uri.is_empty()),
+        }
+    }
+
+    /// Returns all child nodes with the given namespace uri and local name in document order
+    /// Works regardless of which prefix the document used for the namespace
+    pub fn get_nodes_ns(&self, uri: &str, local_name: &str) -> Vec<&Node> {
+        self.items()
+            .filter_map(|item| match item {
+                crate::Item::Node(node)
+                    if node.local_name() == local_name && node.namespace() == Some(uri) =>
+                {
+                    Some(node)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns all child nodes with the given namespace uri and local name
+    /// If no such nodes exist, an Err of TagNotFound is returned containing the parent name and the
+    /// requested name in {uri}local_name notation
+    pub fn try_get_nodes_ns(&self, uri: &str, local_name: &str) -> Result<Vec<&Node>, Error> {
+        match self.get_nodes_ns(uri, local_name) {
+            v if v.is_empty() => Err(Error::TagNotFound(
+                self.tag.to_owned(),
+                format!("{{{}}}{}", uri, local_name),
+            )),
+            v => Ok(v),
+        }
+    }
+
+    /// Gets an attribute by namespace uri and local name
+    /// Attributes without a prefix have no namespace, and can be found with get_attribute
+    pub fn get_attribute_ns(&self, uri: &str, local_name: &str) -> Option<&String> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| match k.split_once(':') {
+                Some((prefix, name)) => {
+                    prefix != "xmlns"
+                        && name == local_name
+                        && self.lookup_namespace(prefix) == Some(uri)
+                }
+                None => false,
+            })
+            .map(|(_, v)| v)
+    }
+}
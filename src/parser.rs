@@ -0,0 +1,523 @@
+//! This is a module providing the tree builder, which turns the tokens of a whole input into a document
+//! The input is read in a single pass, and open nodes are kept on an explicit stack
+
+use crate::check;
+use crate::document::{load_declaration, Document, Misc};
+use crate::item::Entry;
+use crate::namespace::Scope;
+use crate::tokenizer::{self, Scan, Token};
+use crate::{Error, Node, ParseError, ParseOptions, Position};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// A node the builder can produce, either an owned Node or a BorrowedNode referencing the input
+pub(crate) trait Tree<'a>: Sized {
+    fn open(tag: &'a str) -> Self;
+
+    /// Sets the attributes, and the namespace scope when parsing with namespaces
+    fn set_attributes(&mut self, attributes: Vec<(&'a str, Cow<'a, str>)>, scope: Option<Scope>);
+
+    fn tag(&self) -> &str;
+
+    fn push_text(&mut self, text: Cow<'a, str>);
+
+    fn push_cdata(&mut self, data: &'a str);
+
+    fn push_comment(&mut self, comment: &'a str);
+
+    fn push_node(&mut self, node: Self);
+
+    /// Removes the whitespace which only lays out the children once the node is closed, see layout
+    fn trim(&mut self);
+}
+
+/// A child of a node as seen by layout
+pub(crate) enum Child<'s> {
+    Text(&'s str),
+    CData,
+    Node,
+    Comment,
+}
+
+/// Returns the range of every text run of a node which is kept, empty if the run is removed
+/// Whitespace at the start and end of the node is removed, and so is whitespace between children
+/// unless the node has text or CDATA of its own, as in mixed content it separates words
+pub(crate) fn layout(children: &[Child]) -> Vec<Range<usize>> {
+    let is_text = |child: &Child| match child {
+        Child::Text(text) => !text.trim().is_empty(),
+        Child::CData => true,
+        _ => false,
+    };
+    let is_content = |child: &Child| is_text(child) || matches!(child, Child::Node);
+    let mixed = children.iter().any(is_text);
+    let first = children.iter().position(is_content).unwrap_or(0);
+    let last = children.iter().rposition(is_content).unwrap_or(0);
+
+    let runs = children
+        .iter()
+        .enumerate()
+        .filter_map(|(i, child)| match child {
+            Child::Text(text) => Some((i, text)),
+            _ => None,
+        });
+    runs.map(|(i, text)| {
+        if !mixed {
+            return 0..0;
+        }
+        let start = match i <= first {
+            true => text.len() - text.trim_start().len(),
+            false => 0,
+        };
+        let end = match i >= last {
+            true => text.trim_end().len(),
+            false => text.len(),
+        };
+        start..end.max(start)
+    })
+    .collect()
+}
+
+/// Removes whitespace from the text of a node as it arrives, before the node is complete
+/// Follows layout, except that whitespace between child nodes is only kept once the node has text
+/// of its own before it, since whether any follows is not known yet
+#[derive(Debug, Default)]
+pub(crate) struct Spacing {
+    /// Whether the node has text or CDATA so far
+    mixed: bool,
+    /// Whether the node has any children but comments so far
+    content: bool,
+    /// Whitespace which is kept only if more content follows
+    space: String,
+}
+
+impl Spacing {
+    /// Returns what is kept of a run of text, including whitespace held back before it
+    /// Returns None if nothing can be added yet
+    pub(crate) fn text(&mut self, text: &str) -> Option<String> {
+        if text.trim().is_empty() {
+            if self.mixed {
+                self.space.push_str(text);
+            }
+            return None;
+        }
+
+        let start = match self.content {
+            true => 0,
+            false => text.len() - text.trim_start().len(),
+        };
+        let end = text.trim_end().len();
+        let mut kept = std::mem::take(&mut self.space);
+        kept.push_str(&text[start..end]);
+        self.space.push_str(&text[end..]);
+        self.mixed = true;
+        self.content = true;
+        Some(kept)
+    }
+
+    /// Returns the whitespace held back before a child node or CDATA section, if any
+    pub(crate) fn child(&mut self, cdata: bool) -> Option<String> {
+        self.mixed |= cdata;
+        self.content = true;
+        match self.space.is_empty() {
+            true => None,
+            false => Some(std::mem::take(&mut self.space)),
+        }
+    }
+}
+
+impl<'a> Tree<'a> for Node {
+    fn open(tag: &'a str) -> Self {
+        crate::new(tag, String::new())
+    }
+
+    fn set_attributes(&mut self, attributes: Vec<(&'a str, Cow<'a, str>)>, scope: Option<Scope>) {
+        self.attributes = attributes
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v.into_owned()))
+            .collect();
+        self.namespaces = scope;
+    }
+
+    fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn push_text(&mut self, text: Cow<'a, str>) {
+        self.content.push_str(&text);
+        self.order.push(Entry::Text(text.into_owned()));
+    }
+
+    fn push_cdata(&mut self, data: &'a str) {
+        self.content.push_str(data);
+        self.order.push(Entry::CData(data.to_owned()));
+    }
+
+    fn push_comment(&mut self, comment: &'a str) {
+        self.order.push(Entry::Comment(comment.to_owned()));
+    }
+
+    fn push_node(&mut self, node: Self) {
+        self.add_node(node);
+    }
+
+    fn trim(&mut self) {
+        let children: Vec<_> = self
+            .order
+            .iter()
+            .map(|entry| match entry {
+                Entry::Node(..) => Child::Node,
+                Entry::Text(text) => Child::Text(text),
+                Entry::CData(_) => Child::CData,
+                Entry::Comment(_) => Child::Comment,
+            })
+            .collect();
+        let mut ranges = layout(&children).into_iter();
+
+        self.content.clear();
+        for entry in &mut self.order {
+            match entry {
+                Entry::Text(text) => {
+                    let range = ranges.next().unwrap();
+                    text.truncate(range.end);
+                    text.drain(..range.start);
+                    self.content.push_str(text);
+                }
+                Entry::CData(data) => self.content.push_str(data),
+                _ => {}
+            }
+        }
+        self.order
+            .retain(|entry| !matches!(entry, Entry::Text(text) if text.is_empty()));
+    }
+}
+
+/// A node whose closing tag has not been read yet
+struct Open<'a, T> {
+    node: T,
+    /// The input from the opening tag onwards, used to position errors
+    start: &'a str,
+    /// The namespace declarations in scope, only set when parsing with namespaces
+    scope: Option<Scope>,
+    /// The length of the path of the parent
+    path_len: usize,
+}
+
+/// The state of loading a document
+struct Builder<'a, T> {
+    /// The whole input, used to position errors
+    source: &'a str,
+    options: ParseOptions,
+    /// The errors recovered from, or None if parsing stops at the first error
+    recovered: Option<Vec<Error>>,
+    /// The byte offsets at which lines start, built when the first error is positioned
+    lines: Option<Vec<usize>>,
+    stack: Vec<Open<'a, T>>,
+    /// The tags of the open nodes joined by '/', kept along with the stack so errors don't rebuild it
+    path: String,
+    /// The declaration and everything around the root, which is kept in root instead of document.root
+    document: Document,
+    root: Option<T>,
+}
+
+/// Loads a document from a string
+/// Only comments, processing instructions and a document type declaration may appear around the root node
+/// When recovering, errors are collected instead of returned, and anything misplaced is skipped
+pub(crate) fn load(
+    source: &str,
+    options: &ParseOptions,
+    recover: bool,
+) -> Result<(Document, Vec<Error>), Error> {
+    let (mut document, root, errors) = build::<Node>(source, options, recover)?;
+    document.root = root;
+    Ok((document, errors))
+}
+
+/// Loads a document from a string into any kind of tree, see load
+/// The root is returned separately from the document, which holds an empty root node
+/// If the input has no root node, an empty node is returned
+pub(crate) fn build<'a, T: Tree<'a>>(
+    source: &'a str,
+    options: &ParseOptions,
+    recover: bool,
+) -> Result<(Document, T, Vec<Error>), Error> {
+    let mut builder: Builder<'a, T> = Builder {
+        source,
+        options: *options,
+        recovered: if recover { Some(Vec::new()) } else { None },
+        lines: None,
+        stack: Vec::new(),
+        path: String::new(),
+        document: Document {
+            version: None,
+            encoding: None,
+            standalone: None,
+            doctype: None,
+            prolog: Vec::new(),
+            root: crate::new("", String::new()),
+            epilog: Vec::new(),
+        },
+        root: None,
+    };
+
+    // Skip the byte order mark
+    let mut pos = match source.starts_with('\u{feff}') {
+        true => '\u{feff}'.len_utf8(),
+        false => 0,
+    };
+    // Adjacent runs of text, such as around a stray '<', are joined before they are added
+    let mut text = pos..pos;
+    // Whether anything but whitespace came before the current token
+    let mut at_start = true;
+
+    loop {
+        let input = &source[pos..];
+        let (token, len, error) = match tokenizer::scan(input, true) {
+            Scan::Token(token, len, error) => (token, len, error),
+            Scan::End => break,
+            // Nothing may follow, so the rest of the input is kept as text rather than dropped
+            Scan::Incomplete => (
+                Token::Text(input),
+                input.len(),
+                Some(ParseError::MissingClosingDelimiter),
+            ),
+        };
+
+        if let Some(e) = error {
+            builder.report(e, input)?;
+        }
+        pos += len;
+
+        if let Token::Text(_) = token {
+            if text.is_empty() {
+                text.start = pos - len;
+            }
+            text.end = pos;
+            continue;
+        }
+
+        if !text.is_empty() {
+            at_start &= source[text.clone()].trim().is_empty();
+            builder.text(&source[text.clone()])?;
+        }
+        text = pos..pos;
+        builder.token(token, input, at_start)?;
+        at_start = false;
+    }
+
+    if !text.is_empty() {
+        builder.text(&source[text])?;
+    }
+
+    // Nodes which are still open end with the input, where only the innermost one is reported
+    if let Some(open) = builder.stack.last() {
+        builder.report(
+            ParseError::MissingClosingTag(open.node.tag().to_owned()),
+            open.start,
+        )?;
+    }
+    while !builder.stack.is_empty() {
+        builder.close();
+    }
+
+    let mut errors = builder.recovered.unwrap_or_default();
+    errors.sort_by_key(|e| e.position().map(|position| position.offset));
+    let root = builder.root.unwrap_or_else(|| T::open(""));
+    Ok((builder.document, root, errors))
+}
+
+impl<'a, T: Tree<'a>> Builder<'a, T> {
+    /// Returns the error positioned at the start of at, which must be a subslice of source
+    /// When recovering, the error is recorded and Ok is returned so the caller can carry on
+    fn report(&mut self, kind: ParseError, at: &str) -> Result<(), Error> {
+        let offset = at.as_ptr() as usize - self.source.as_ptr() as usize;
+        let position = self.position(offset);
+        let e = Error::ParseError(kind, position);
+        match &mut self.recovered {
+            Some(errors) => {
+                errors.push(e);
+                Ok(())
+            }
+            None => Err(e),
+        }
+    }
+
+    /// Reports content outside the root node, which starts at at
+    fn outside_root(&mut self, at: &str) -> Result<(), Error> {
+        self.report(ParseError::ContentOutsideRoot, at)
+    }
+
+    /// Calculates the position of a byte offset using an index of line starts
+    /// Unlike Position::new, this does not scan the whole input before the offset for every error
+    fn position(&mut self, offset: usize) -> Position {
+        let source = self.source;
+        let lines = self.lines.get_or_insert_with(|| {
+            std::iter::once(0)
+                .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+                .collect()
+        });
+        let line = lines.partition_point(|&start| start <= offset);
+        Position {
+            line,
+            column: source[lines[line - 1]..offset].chars().count() + 1,
+            offset,
+            path: self.path.clone(),
+        }
+    }
+
+    /// Decodes the entities in a piece of text, see check::decode
+    fn decode<'b>(&mut self, string: &'b str) -> Result<Cow<'b, str>, Error> {
+        let options = self.options;
+        check::decode(string, &options, &mut |e, at| self.report(e, at))
+    }
+
+    /// Adds a run of text to the current node, where whitespace is removed once the node is closed
+    /// Only whitespace may appear outside the root node
+    fn text(&mut self, text: &'a str) -> Result<(), Error> {
+        if self.stack.is_empty() {
+            let text = text.trim();
+            return match text.is_empty() {
+                true => Ok(()),
+                false => self.outside_root(text),
+            };
+        }
+
+        let text = self.decode(text)?;
+        self.stack.last_mut().unwrap().node.push_text(text);
+        Ok(())
+    }
+
+    /// Handles any token but text
+    /// input is the input from the token onwards, and at_start is true if only whitespace came before it
+    fn token(&mut self, token: Token<'a>, input: &'a str, at_start: bool) -> Result<(), Error> {
+        match token {
+            Token::Text(text) => self.text(text)?,
+            Token::StartTag {
+                name,
+                attributes,
+                empty,
+            } => {
+                // A second root node is still read, and dropped when it is closed
+                if self.stack.is_empty() && self.root.is_some() {
+                    self.outside_root(input)?;
+                }
+                self.open(name, attributes, input)?;
+                if empty {
+                    self.close();
+                }
+            }
+            Token::EndTag(name) => self.end_tag(name)?,
+            Token::Comment(comment) if self.options.keep_comments => match self.stack.last_mut() {
+                Some(open) => open.node.push_comment(comment),
+                None => self.misc(Misc::Comment(comment.to_owned())),
+            },
+            Token::Comment(_) => {}
+            Token::CData(data) => match self.stack.last_mut() {
+                Some(open) => open.node.push_cdata(data),
+                None => self.outside_root(input)?,
+            },
+            // The xml declaration is only valid at the very start, and skipped elsewhere when recovering
+            Token::ProcessingInstruction(pi) if !at_start && check::is_declaration(pi) => {
+                self.report(ParseError::InvalidDeclaration(String::new()), input)?
+            }
+            // Processing instructions and declarations inside nodes are skipped
+            Token::ProcessingInstruction(_) | Token::Doctype(_) if !self.stack.is_empty() => {}
+            Token::ProcessingInstruction(pi) => {
+                let (target, data) = check::processing_instruction(pi);
+                if target == "xml" {
+                    if let Err(e) = load_declaration(&mut self.document, data) {
+                        self.report(e, input)?;
+                    }
+                } else {
+                    self.misc(Misc::ProcessingInstruction(
+                        target.to_owned(),
+                        data.to_owned(),
+                    ));
+                }
+            }
+            Token::Doctype(_) if self.root.is_some() || self.document.doctype.is_some() => {
+                self.outside_root(input)?
+            }
+            Token::Doctype(doctype) => self.document.doctype = Some(doctype.to_owned()),
+        }
+        Ok(())
+    }
+
+    /// Adds a comment or processing instruction around the root node
+    fn misc(&mut self, misc: Misc) {
+        match self.root {
+            None => self.document.prolog.push(misc),
+            Some(_) => self.document.epilog.push(misc),
+        }
+    }
+
+    /// Pushes a new node onto the stack
+    /// Malformed attributes are skipped when recovering
+    fn open(&mut self, name: &'a str, raw: &'a str, input: &'a str) -> Result<(), Error> {
+        let parent = self.stack.last().and_then(|open| open.scope.clone());
+        let path_len = self.path.len();
+        if !self.stack.is_empty() {
+            self.path.push('/');
+        }
+        self.path.push_str(name);
+        self.stack.push(Open {
+            node: T::open(name),
+            start: input,
+            scope: None,
+            path_len,
+        });
+
+        let options = self.options;
+        let mut report = |e, at: &str| self.report(e, at);
+        let attributes = check::attributes(raw, &options, &mut report)?;
+        let scope = match options.namespaces {
+            true => Some(check::scope(name, &attributes, parent, input, &mut report)?),
+            false => None,
+        };
+
+        let open = self.stack.last_mut().unwrap();
+        open.scope = scope.clone();
+        open.node.set_attributes(attributes, scope);
+        Ok(())
+    }
+
+    /// Pops the current node and adds it to its parent, or makes it the root
+    /// A second root node is dropped
+    fn close(&mut self) {
+        let open = match self.stack.pop() {
+            Some(v) => v,
+            None => return,
+        };
+        self.path.truncate(open.path_len);
+        let mut node = open.node;
+        node.trim();
+
+        match self.stack.last_mut() {
+            Some(parent) => parent.node.push_node(node),
+            None if self.root.is_none() => self.root = Some(node),
+            None => {}
+        }
+    }
+
+    /// Closes the node with the given tag
+    /// If other nodes were opened inside it and not closed, they end here when recovering, where
+    /// only the innermost one is reported
+    /// A closing tag which matches no open node is skipped when recovering
+    fn end_tag(&mut self, name: &'a str) -> Result<(), Error> {
+        let closed = match check::closes(self.stack.iter().map(|open| open.node.tag()), name) {
+            Ok(v) => v,
+            Err(e) => return self.report(e, name),
+        };
+
+        if closed > 1 {
+            let open = self.stack.last().unwrap();
+            self.report(
+                ParseError::MissingClosingTag(open.node.tag().to_owned()),
+                open.start,
+            )?;
+        }
+        for _ in 0..closed {
+            self.close();
+        }
+        Ok(())
+    }
+}
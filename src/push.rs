@@ -0,0 +1,196 @@
+//! This is a module providing a push parser for xml which arrives in pieces, such as over a socket
+//! Input can be split anywhere, even inside a tag or a character, and whatever is complete can be
+//! taken out as events or as whole child nodes of the root, while the root itself stays open
+//! ```
+//! use szl_simple_xml::push::PushParser;
+//!
+//! let mut parser = PushParser::new();
+//! parser.feed(b"<stream><message><body>hel").unwrap();
+//! assert!(parser.next_node().unwrap().is_none());
+//!
+//! parser.feed(b"lo</body></mess").unwrap();
+//! parser.feed(b"age>").unwrap();
+//! let message = parser.next_node().unwrap().expect("Missing message");
+//! assert_eq!(message["body"][0].content, "hello");
+//! assert_eq!(parser.root().unwrap().tag, "stream");
+//! ```
+
+use crate::parser::{Spacing, Tree};
+use crate::reader::{Event, State};
+use crate::{Attributes, Error, Node, ParseOptions};
+
+/// Parses xml which is fed to it in pieces
+/// Events can be taken out with next_event, or child nodes of the root with next_node, but the two
+/// should not be mixed as both consume the same events
+pub struct PushParser {
+    state: State,
+    /// The root node without the children returned by next_node
+    root: Option<Node>,
+    /// The nodes inside the root which are not closed yet
+    nodes: Nodes,
+    /// The whitespace of the text of the root, which is removed as it arrives since the root stays open
+    spacing: Spacing,
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PushParser {
+    /// Creates a push parser with the default parse options
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    /// Creates a push parser using the given parse options
+    pub fn with_options(options: ParseOptions) -> Self {
+        PushParser {
+            state: State::new(options),
+            root: None,
+            nodes: Nodes::default(),
+            spacing: Spacing::default(),
+        }
+    }
+
+    /// Adds the next piece of input
+    /// Fails if the input is not valid UTF-8, while a character split across pieces is fine
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.state.push(bytes)
+    }
+
+    /// Marks the end of the input, so that unterminated markup is reported
+    pub fn finish(&mut self) -> Result<(), Error> {
+        self.state.finish()
+    }
+
+    /// Returns the depth of the current node, the number of nodes which are open
+    pub fn depth(&self) -> usize {
+        self.state.depth()
+    }
+
+    /// Returns the next complete event, or None if more input is needed
+    /// Eof is only returned after finish
+    pub fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        self.state.next_event()
+    }
+
+    /// Returns the next complete child node of the root, or None if more input is needed or the
+    /// input has ended
+    /// The root itself, with its attributes and text, is available from root
+    pub fn next_node(&mut self) -> Result<Option<Node>, Error> {
+        self.state.raw_text = true;
+        while let Some(event) = self.state.next_event()? {
+            let event = match event {
+                Event::StartElement { name, attributes } if self.root.is_none() => {
+                    self.root = Some(element(&name, attributes, &self.state));
+                    continue;
+                }
+                Event::StartElement { .. } if self.nodes.open.is_empty() => {
+                    self.add_space(false);
+                    event
+                }
+                event => event,
+            };
+
+            let event = match self.nodes.event(event, &self.state) {
+                Step::Added => continue,
+                Step::Closed(node) => return Ok(Some(node)),
+                Step::Outside(Event::Eof) => return Ok(None),
+                Step::Outside(event) => event,
+            };
+
+            // Comments outside the root are skipped
+            let in_root = self.state.depth() > 0;
+            let root = match self.root.as_mut() {
+                Some(root) if in_root => root,
+                _ => continue,
+            };
+            match event {
+                Event::Text(text) => {
+                    if let Some(text) = self.spacing.text(&text) {
+                        root.add_text(&text);
+                    }
+                }
+                Event::CData(data) => {
+                    self.add_space(true);
+                    self.root.as_mut().unwrap().add_cdata(&data);
+                }
+                Event::Comment(comment) => root.add_comment(&comment),
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Adds the whitespace held back in the root before a child node or CDATA section
+    fn add_space(&mut self, cdata: bool) {
+        if let (Some(root), Some(space)) = (self.root.as_mut(), self.spacing.child(cdata)) {
+            root.add_text(&space);
+        }
+    }
+
+    /// Returns the root node once its opening tag has been read
+    /// When using next_node, it does not contain the child nodes which were returned
+    pub fn root(&self) -> Option<&Node> {
+        self.root.as_ref()
+    }
+}
+
+/// What became of an event given to Nodes
+pub(crate) enum Step {
+    /// The event was added to the open nodes
+    Added,
+    /// The outermost open node was closed
+    Closed(Node),
+    /// No node is open, so the event was not used
+    Outside(Event),
+}
+
+/// Builds nodes from events the same way as from_string, for the push parser and async parsing
+/// Text has to be read with State::raw_text, so that whitespace is removed once a node is closed
+#[derive(Default)]
+pub(crate) struct Nodes {
+    /// The nodes which are not closed yet
+    open: Vec<Node>,
+}
+
+impl Nodes {
+    /// Adds an event to the open nodes, or opens a new one
+    pub(crate) fn event(&mut self, event: Event, state: &State) -> Step {
+        if let Event::StartElement { name, attributes } = event {
+            self.open.push(element(&name, attributes, state));
+            return Step::Added;
+        }
+
+        let current = match self.open.last_mut() {
+            Some(v) => v,
+            None => return Step::Outside(event),
+        };
+        match event {
+            Event::EndElement { .. } => {
+                let mut node = self.open.pop().unwrap();
+                node.trim();
+                match self.open.last_mut() {
+                    Some(parent) => parent.add_node(node),
+                    None => return Step::Closed(node),
+                }
+            }
+            Event::Text(text) => current.add_text(&text),
+            Event::CData(data) => current.add_cdata(&data),
+            Event::Comment(comment) => current.add_comment(&comment),
+            // Nothing else is read inside a node
+            event => return Step::Outside(event),
+        }
+        Step::Added
+    }
+}
+
+/// Returns the node of a StartElement, which is the current node of state
+fn element(name: &str, attributes: Attributes, state: &State) -> Node {
+    let mut node = crate::new(name, String::new());
+    node.attributes = attributes;
+    node.namespaces = state.scope();
+    node
+}
@@ -0,0 +1,545 @@
+//! This is a module providing a pull parser which reads xml as a sequence of events
+//! Input is read incrementally from any io::Read, so files larger than memory can be processed
+//! The events are checked and decoded the same way as by from_string, and whitespace is removed the
+//! same way too, apart from whitespace between child nodes before a node has any text of its own
+//! ```
+//! use szl_simple_xml::reader::{Event, Reader};
+//!
+//! let mut reader = Reader::new("<note><to>Tove</to></note>".as_bytes());
+//! let mut names = Vec::new();
+//! loop {
+//!     match reader.next_event().unwrap() {
+//!         Event::StartElement { name, .. } => names.push(name),
+//!         Event::Eof => break,
+//!         _ => {}
+//!     }
+//! }
+//! assert_eq!(names, vec!["note", "to"]);
+//! ```
+
+use crate::check;
+use crate::document::{load_declaration, Document};
+use crate::namespace::Scope;
+use crate::parser::Spacing;
+use crate::tokenizer::{self, Scan, Token};
+use crate::{Attributes, Error, ParseError, ParseOptions, Position};
+use std::io::{self, Read};
+
+/// The number of bytes read at a time
+const CHUNK: usize = 8192;
+
+/// A piece of xml returned by the reader
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// An opening tag, or an empty tag which is followed by its EndElement
+    StartElement {
+        name: String,
+        attributes: Attributes,
+    },
+    EndElement {
+        name: String,
+    },
+    /// A run of text with entities decoded and whitespace removed as by from_string
+    /// Whitespace between child nodes is only returned once the node has text of its own before it,
+    /// and whitespace before a comment is returned after it
+    Text(String),
+    CData(String),
+    /// A comment, only returned when reading with keep_comments
+    Comment(String),
+    /// A processing instruction with its target and data, including the xml declaration
+    ProcessingInstruction(String, String),
+    /// The document type declaration without the surrounding <!DOCTYPE and >
+    Doctype(String),
+    /// The end of the input, returned from then on
+    Eof,
+}
+
+/// A position in the input which is moved forward as the input is consumed
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Cursor {
+    fn advance(&mut self, consumed: &str) {
+        self.offset += consumed.len();
+        match consumed.rfind('\n') {
+            Some(i) => {
+                self.line += consumed.bytes().filter(|&c| c == b'\n').count();
+                self.column = consumed[i + 1..].chars().count() + 1;
+            }
+            None => self.column += consumed.chars().count(),
+        }
+    }
+}
+
+/// A node whose EndElement has not been returned yet
+struct Open {
+    name: String,
+    /// Where the opening tag starts, used to position errors
+    start: Cursor,
+    /// The namespace declarations in scope, only set when reading with namespaces
+    scope: Option<Scope>,
+    spacing: Spacing,
+}
+
+/// Reads events from xml in an io::Read
+/// The reader buffers its input, so there is no need to wrap it in a BufReader
+pub struct Reader<R: Read> {
+    reader: R,
+    state: State,
+}
+
+impl<R: Read> Reader<R> {
+    /// Creates a reader with the default parse options
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParseOptions::default())
+    }
+
+    /// Creates a reader using the given parse options
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        Reader {
+            reader,
+            state: State::new(options),
+        }
+    }
+
+    /// Returns the depth of the current node, the number of nodes which are open
+    pub fn depth(&self) -> usize {
+        self.state.depth()
+    }
+
+    /// Returns the position of the next unread input
+    pub fn position(&self) -> Position {
+        self.state.position()
+    }
+
+    /// Reads the next event
+    /// After an error, Eof is returned
+    pub fn next_event(&mut self) -> Result<Event, Error> {
+        loop {
+            if let Some(event) = self.state.next_event()? {
+                return Ok(event);
+            }
+            if let Err(e) = self.fill() {
+                self.state.done = true;
+                return Err(e);
+            }
+        }
+    }
+
+    /// Reads more input
+    /// At least as much as is buffered is read, so a long token is scanned a bounded number of times
+    fn fill(&mut self) -> Result<(), Error> {
+        let want = self.state.unread().max(CHUNK);
+        let mut chunk = vec![0; want];
+        let mut read = 0;
+        while read < want {
+            let n = match self.reader.read(&mut chunk) {
+                Ok(v) => v,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if n == 0 {
+                return self.state.finish();
+            }
+            read += n;
+            self.state.push(&chunk[..n])?;
+
+            // A small token only needs one read
+            if want == CHUNK {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Iterates the events up to, but not including, Eof
+/// Iteration ends after the first error
+impl<R: Read> Iterator for Reader<R> {
+    type Item = Result<Event, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Ok(Event::Eof) => None,
+            v => Some(v),
+        }
+    }
+}
+
+/// The state of reading events from input which is pushed in pieces
+/// Shared by Reader and the push parser
+pub(crate) struct State {
+    options: ParseOptions,
+    /// Input pushed but not consumed yet, starting at pos
+    buf: String,
+    pos: usize,
+    /// The bytes of a character split across pushes
+    partial: Vec<u8>,
+    eof: bool,
+    /// The position of buf[pos] in the whole input
+    cursor: Cursor,
+    stack: Vec<Open>,
+    has_root: bool,
+    has_doctype: bool,
+    /// Whether only whitespace has been read so far
+    at_start: bool,
+    /// The EndElement of an empty tag, returned after its StartElement
+    pending: Option<Event>,
+    /// Set after Eof or an error
+    pub(crate) done: bool,
+    /// Return text inside nodes as it appears, for building nodes which remove whitespace once closed
+    pub(crate) raw_text: bool,
+    /// The xml declaration once it is read, for building a document
+    pub(crate) declaration: Option<Document>,
+}
+
+impl State {
+    pub(crate) fn new(options: ParseOptions) -> Self {
+        State {
+            options,
+            buf: String::new(),
+            pos: 0,
+            partial: Vec::new(),
+            eof: false,
+            cursor: Cursor {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            stack: Vec::new(),
+            has_root: false,
+            has_doctype: false,
+            at_start: true,
+            pending: None,
+            done: false,
+            raw_text: false,
+            declaration: None,
+        }
+    }
+
+    /// Returns the depth of the current node
+    pub(crate) fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Returns the position of the next unread input
+    pub(crate) fn position(&self) -> Position {
+        self.position_at(self.cursor)
+    }
+
+    /// Returns the namespace declarations in scope of the current node
+    pub(crate) fn scope(&self) -> Option<Scope> {
+        self.stack.last().and_then(|open| open.scope.clone())
+    }
+
+    /// Returns the number of bytes pushed but not consumed yet
+    pub(crate) fn unread(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// Returns the next event, or None if more input is needed
+    /// After an error, Eof is returned
+    pub(crate) fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        // The node of an empty tag is closed once its EndElement is returned
+        if let Some(event) = self.pending.take() {
+            self.close();
+            return Ok(Some(event));
+        }
+
+        while !self.done {
+            match self.step() {
+                None => return Ok(None),
+                Some(Ok(Some(event))) => return Ok(Some(event)),
+                Some(Ok(None)) => {}
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(Some(Event::Eof))
+    }
+
+    /// Adds input after what was pushed before
+    /// A character may be split across pushes
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        // Drop consumed input once it makes up most of the buffer
+        if self.pos > self.buf.len() / 2 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        // Invalid input is not kept, so the next push can carry on
+        let kept = self.partial.len();
+        self.partial.extend_from_slice(bytes);
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(v) => v.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => {
+                self.partial.truncate(kept);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e).into());
+            }
+        };
+        self.buf
+            .push_str(std::str::from_utf8(&self.partial[..valid]).unwrap());
+        self.partial.drain(..valid);
+        Ok(())
+    }
+
+    /// Marks the end of the input
+    pub(crate) fn finish(&mut self) -> Result<(), Error> {
+        self.eof = true;
+        if !self.partial.is_empty() {
+            let e = io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            );
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Consumes the next token, returning the event it results in if any
+    /// Returns None if more input is needed
+    fn step(&mut self) -> Option<Result<Option<Event>, Error>> {
+        // Skip the byte order mark
+        if self.cursor.offset == 0 && self.pos == 0 {
+            if self.buf.is_empty() && !self.eof {
+                return None;
+            }
+            if self.buf.starts_with('\u{feff}') {
+                self.consume('\u{feff}'.len_utf8());
+            }
+        }
+
+        // The buffer is taken out so tokens can borrow from it while the reader changes
+        let buf = std::mem::take(&mut self.buf);
+        let input = &buf[self.pos..];
+        let result = match tokenizer::scan(input, self.eof) {
+            // Once the input has ended, unconsumed input can not be left waiting for more
+            Scan::Incomplete if self.eof => Some(Err(
+                self.error(ParseError::MissingClosingDelimiter, self.cursor)
+            )),
+            Scan::Incomplete => None,
+            Scan::End => Some(self.end().map(Some)),
+            Scan::Token(_, _, Some(e)) => Some(Err(self.error(e, self.cursor))),
+            Scan::Token(token, len, None) => match self.held_space(&token) {
+                // The child is read again after the whitespace before it is returned
+                Some(space) => Some(Ok(Some(Event::Text(space)))),
+                None => {
+                    let event = self.token(token, input);
+                    self.consume_in(&buf, len);
+                    Some(event)
+                }
+            },
+        };
+        self.buf = buf;
+        result
+    }
+
+    /// Returns the whitespace held back in the current node if the token is a child node or CDATA
+    fn held_space(&mut self, token: &Token) -> Option<String> {
+        let cdata = match token {
+            _ if self.raw_text => return None,
+            Token::StartTag { .. } => false,
+            Token::CData(_) => true,
+            _ => return None,
+        };
+        self.stack.last_mut()?.spacing.child(cdata)
+    }
+
+    /// Returns the event for a token which starts at input, or None if the token is skipped
+    fn token(&mut self, token: Token, input: &str) -> Result<Option<Event>, Error> {
+        if let Token::Text(text) = token {
+            let blank = text.trim().is_empty();
+            if blank && (!self.raw_text || self.stack.is_empty()) {
+                if let Some(open) = self.stack.last_mut() {
+                    open.spacing.text(text);
+                }
+                return Ok(None);
+            }
+            if !blank {
+                self.at_start = false;
+            }
+            if self.stack.is_empty() {
+                let cursor = self.cursor_at(input, text.trim_start());
+                return Err(self.error(ParseError::ContentOutsideRoot, cursor));
+            }
+            let text = check::decode(text, &self.options, &mut self.strict(input))?;
+            if self.raw_text {
+                return Ok(Some(Event::Text(text.into_owned())));
+            }
+            let open = self.stack.last_mut().unwrap();
+            return Ok(open.spacing.text(&text).map(Event::Text));
+        }
+
+        let at_start = std::mem::replace(&mut self.at_start, false);
+        let event = match token {
+            Token::Text(_) => None,
+            Token::StartTag {
+                name,
+                attributes,
+                empty,
+            } => {
+                if self.stack.is_empty() && self.has_root {
+                    return Err(self.error(ParseError::ContentOutsideRoot, self.cursor));
+                }
+                let attributes = self.open(name, attributes, input)?;
+                if empty {
+                    self.pending = Some(Event::EndElement {
+                        name: name.to_owned(),
+                    });
+                }
+                Some(Event::StartElement {
+                    name: name.to_owned(),
+                    attributes,
+                })
+            }
+            Token::EndTag(name) => {
+                let open = self.stack.iter().map(|open| open.name.as_str());
+                match check::closes(open, name) {
+                    Ok(1) => {}
+                    Ok(_) => {
+                        let open = self.stack.last().unwrap();
+                        let kind = ParseError::MissingClosingTag(open.name.clone());
+                        return Err(self.error(kind, open.start));
+                    }
+                    Err(e) => return Err(self.error(e, self.cursor_at(input, name))),
+                }
+                self.close();
+                Some(Event::EndElement {
+                    name: name.to_owned(),
+                })
+            }
+            Token::Comment(comment) if self.options.keep_comments => {
+                Some(Event::Comment(comment.to_owned()))
+            }
+            Token::Comment(_) => None,
+            Token::CData(_) if self.stack.is_empty() => {
+                return Err(self.error(ParseError::ContentOutsideRoot, self.cursor))
+            }
+            Token::CData(data) => Some(Event::CData(data.to_owned())),
+            // The xml declaration is only valid at the very start
+            Token::ProcessingInstruction(pi) if !at_start && check::is_declaration(pi) => {
+                let kind = ParseError::InvalidDeclaration(String::new());
+                return Err(self.error(kind, self.cursor));
+            }
+            // Processing instructions and declarations inside nodes are skipped
+            Token::ProcessingInstruction(_) | Token::Doctype(_) if !self.stack.is_empty() => None,
+            Token::ProcessingInstruction(pi) => {
+                let (target, data) = check::processing_instruction(pi);
+                if target == "xml" {
+                    let mut document = Document::new(crate::new("", String::new()));
+                    document.version = None;
+                    if let Err(e) = load_declaration(&mut document, data) {
+                        return Err(self.error(e, self.cursor));
+                    }
+                    self.declaration = Some(document);
+                }
+                Some(Event::ProcessingInstruction(
+                    target.to_owned(),
+                    data.to_owned(),
+                ))
+            }
+            Token::Doctype(_) if self.has_root || self.has_doctype => {
+                return Err(self.error(ParseError::ContentOutsideRoot, self.cursor))
+            }
+            Token::Doctype(doctype) => {
+                self.has_doctype = true;
+                Some(Event::Doctype(doctype.to_owned()))
+            }
+        };
+
+        Ok(event)
+    }
+
+    /// Pushes a new node and returns its attributes
+    fn open(&mut self, name: &str, raw: &str, input: &str) -> Result<Attributes, Error> {
+        let parent = self.stack.last().and_then(|open| open.scope.clone());
+        self.stack.push(Open {
+            name: name.to_owned(),
+            start: self.cursor,
+            scope: None,
+            spacing: Spacing::default(),
+        });
+
+        let (attributes, scope) = {
+            let mut report = self.strict(input);
+            let attributes = check::attributes(raw, &self.options, &mut report)?;
+            let scope = match self.options.namespaces {
+                true => Some(check::scope(name, &attributes, parent, input, &mut report)?),
+                false => None,
+            };
+            (attributes, scope)
+        };
+        self.stack.last_mut().unwrap().scope = scope;
+
+        Ok(attributes
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v.into_owned()))
+            .collect())
+    }
+
+    /// Pops the current node
+    fn close(&mut self) {
+        self.stack.pop();
+        if self.stack.is_empty() {
+            self.has_root = true;
+        }
+    }
+
+    /// Checks that every node was closed at the end of the input
+    fn end(&mut self) -> Result<Event, Error> {
+        if let Some(open) = self.stack.last() {
+            let kind = ParseError::MissingClosingTag(open.name.clone());
+            return Err(self.error(kind, open.start));
+        }
+        self.done = true;
+        Ok(Event::Eof)
+    }
+
+    /// Returns the report function for the checks of a token which starts at input
+    /// The reader stops at the first error, positioned at the subslice of input it was found at
+    fn strict<'s>(
+        &'s self,
+        input: &'s str,
+    ) -> impl FnMut(ParseError, &str) -> Result<(), Error> + 's {
+        move |kind, at| Err(self.error(kind, self.cursor_at(input, at)))
+    }
+
+    /// Returns the cursor at the start of at, which must be a subslice of input, the token being read
+    fn cursor_at(&self, input: &str, at: &str) -> Cursor {
+        let mut cursor = self.cursor;
+        cursor.advance(&input[..at.as_ptr() as usize - input.as_ptr() as usize]);
+        cursor
+    }
+
+    fn position_at(&self, cursor: Cursor) -> Position {
+        let path: Vec<_> = self.stack.iter().map(|open| open.name.as_str()).collect();
+        Position {
+            line: cursor.line,
+            column: cursor.column,
+            offset: cursor.offset,
+            path: path.join("/"),
+        }
+    }
+
+    fn error(&self, kind: ParseError, cursor: Cursor) -> Error {
+        Error::ParseError(kind, self.position_at(cursor))
+    }
+
+    fn consume(&mut self, len: usize) {
+        let buf = std::mem::take(&mut self.buf);
+        self.consume_in(&buf, len);
+        self.buf = buf;
+    }
+
+    fn consume_in(&mut self, buf: &str, len: usize) {
+        self.cursor.advance(&buf[self.pos..self.pos + len]);
+        self.pos += len;
+    }
+}
@@ -0,0 +1,132 @@
+//! This is a module providing compiler style rendering of errors with an excerpt of the source
+//! ```text
+//! error[missing-closing-tag]: missing closing tag </mesh>
+//!  --> line 3, column 5 (in COLLADA/library_geometries/mesh)
+//!   |
+//! 2 |   <library_geometries>
+//! 3 |     <mesh>
+//!   |     ^^^^^^
+//! ```
+
+use crate::{Error, ParseError};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders an error with the offending line of source underlined
+/// source must be the input the error was returned for
+/// If color is true, ANSI escape codes are used to highlight the output
+/// Errors without a position, such as io errors, are rendered as a single line
+pub fn render(source: &str, error: &Error, color: bool) -> String {
+    let paint = |style: &str, text: &str| match color {
+        true => format!("{}{}{}", style, text, RESET),
+        false => text.to_owned(),
+    };
+
+    let mut out = format!(
+        "{}{}\n",
+        paint(RED, &format!("error[{}]", error.kind())),
+        paint(BOLD, &format!(": {}", message(error))),
+    );
+
+    let (kind, position) = match error {
+        Error::ParseError(kind, position) if position.offset <= source.len() => (kind, position),
+        _ => return out,
+    };
+
+    // The position may not match the source, in which case the lines which don't exist are skipped
+    let lines: Vec<&str> = source.split('\n').collect();
+    let line_at = |number: usize| {
+        let line = *lines.get(number.checked_sub(1)?)?;
+        Some(line.strip_suffix('\r').unwrap_or(line))
+    };
+    let width = (position.line).to_string().len();
+    let gutter = |number: &str| paint(BLUE, &format!("{:>width$} |", number, width = width));
+
+    let location = match position.path.is_empty() {
+        true => position.to_string(),
+        false => format!("{} (in {})", position, position.path),
+    };
+    out.push_str(&format!(
+        "{}{} {}\n",
+        " ".repeat(width),
+        paint(BLUE, "-->"),
+        location
+    ));
+    out.push_str(&format!("{}\n", gutter("")));
+
+    let line = match line_at(position.line) {
+        Some(v) => v,
+        None => return out,
+    };
+
+    // One line of context before the error
+    if let Some(previous) = position.line.checked_sub(1).and_then(line_at) {
+        out.push_str(&format!(
+            "{} {}\n",
+            gutter(&(position.line - 1).to_string()),
+            previous
+        ));
+    }
+    out.push_str(&format!(
+        "{} {}\n",
+        gutter(&position.line.to_string()),
+        line
+    ));
+
+    // Keep tabs so the carets line up with the source
+    let indent: String = line
+        .chars()
+        .take(position.column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let rest = source.get(position.offset..).unwrap_or("");
+    let len = span_len(kind, rest).max(1);
+    out.push_str(&format!(
+        "{} {}{}\n",
+        gutter(""),
+        indent,
+        paint(RED, &"^".repeat(len))
+    ));
+
+    out
+}
+
+/// The message of an error without its position
+fn message(error: &Error) -> String {
+    match error {
+        Error::ParseError(kind, _) => kind.to_string(),
+        e => e.to_string(),
+    }
+}
+
+/// Returns the number of characters to underline for an error starting at rest
+/// The span never continues past the end of the line
+fn span_len(kind: &ParseError, rest: &str) -> usize {
+    let line = rest.split('\n').next().unwrap_or("");
+    let len = match kind {
+        ParseError::MissingAttributeValue(part)
+        | ParseError::MissingQuotes(part)
+        | ParseError::MissingWhitespace(part)
+        | ParseError::InvalidEntity(part) => part.chars().count(),
+        ParseError::UnexpectedClosingTag(tag) => tag.chars().count(),
+        // Underline the opening tag up to its end or the first whitespace
+        ParseError::MissingClosingTag(_) | ParseError::UnboundPrefix(_) => {
+            match line.find(|c: char| c.is_whitespace() || c == '>') {
+                Some(i) if line[i..].starts_with('>') => line[..=i].chars().count(),
+                Some(i) => line[..i].chars().count(),
+                None => line.chars().count(),
+            }
+        }
+        ParseError::MissingClosingDelimiter
+        | ParseError::MissingCommentEnd
+        | ParseError::MissingCDataEnd => 1,
+        ParseError::InvalidDeclaration(_) => line.find("?>").map_or(1, |i| i + 2),
+        ParseError::InvalidTagName(name) => name.chars().count() + 1,
+        // Underline the text or tag up to the end of the line
+        ParseError::ContentOutsideRoot => line.trim_end().chars().count(),
+    };
+    len.min(line.chars().count())
+}
@@ -0,0 +1,552 @@
+//! This is a module providing CSS selectors for finding nodes
+//! Supported are type and universal selectors, attribute selectors with =, ~=, |=, ^=, $= and *=,
+//! #id and .class as shorthands for the id and class attributes, the descendant, child and sibling
+//! combinators, selector lists, and the structural pseudo-classes such as :nth-child(2n+1),
+//! :first-of-type, :last-child, :only-child, :empty and :root
+//! A colon in a tag name has to be escaped, as in xsi\:type
+//! ```
+//! let cube = szl_simple_xml::from_file("examples/cube.dae").unwrap();
+//! let ids: Vec<_> = cube
+//!     .select("library_geometries > geometry[id$='-mesh'] source")
+//!     .unwrap()
+//!     .map(|source| source.get_attribute("id").unwrap().as_str())
+//!     .collect();
+//! assert_eq!(ids, vec!["Cube-mesh-positions", "Cube-mesh-normals", "Cube-mesh-map-0"]);
+//!
+//! let param = cube.select_first("accessor > param:nth-child(2)").unwrap().unwrap();
+//! assert_eq!(param.get_attribute("name").unwrap(), "Y");
+//! ```
+
+use crate::{Error, Item, Node};
+
+/// A parsed selector list, which can be used to select from any number of nodes
+#[derive(Debug, Clone)]
+pub struct Selector {
+    /// The comma separated selectors, of which any has to match
+    complex: Vec<Complex>,
+}
+
+/// Compound selectors joined by combinators, such as a > b c
+#[derive(Debug, Clone)]
+struct Complex {
+    compounds: Vec<Compound>,
+    /// The combinator between each compound and the next
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+    /// +, the directly preceding sibling
+    Adjacent,
+    /// ~, any preceding sibling
+    Sibling,
+}
+
+/// Simple selectors which all have to match the same node, such as geometry[id]:first-child
+#[derive(Debug, Clone, Default)]
+struct Compound {
+    /// The tag, or None for * or no type selector
+    tag: Option<String>,
+    attributes: Vec<Attribute>,
+    pseudo: Vec<Pseudo>,
+}
+
+#[derive(Debug, Clone)]
+struct Attribute {
+    key: String,
+    op: Op,
+    value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    /// [key], which only checks that the attribute exists
+    Exists,
+    Equals,
+    /// ~=, one of the whitespace separated words
+    Word,
+    /// |=, equal or followed by a hyphen
+    Dash,
+    Prefix,
+    Suffix,
+    Substring,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pseudo {
+    /// Matches at positions a*n+b for any n >= 0, counting from 1
+    Nth {
+        a: i64,
+        b: i64,
+        /// Only count siblings with the same tag
+        of_type: bool,
+        /// Count from the last sibling
+        last: bool,
+    },
+    Only {
+        of_type: bool,
+    },
+    Empty,
+    Root,
+}
+
+impl Selector {
+    /// Parses a selector list
+    /// Fails with InvalidSelector on syntax errors and unsupported pseudo-classes
+    pub fn new(selector: &str) -> Result<Self, Error> {
+        let mut parser = Parser {
+            input: selector,
+            pos: 0,
+        };
+        parser
+            .list()
+            .map(|complex| Selector { complex })
+            .map_err(|message| Error::InvalidSelector(selector.to_owned(), message))
+    }
+
+    /// Returns node and all nodes inside it which match, in document order
+    /// Combinators only look at node and what is inside it, as it has no parent
+    pub fn select<'a>(&self, node: &'a Node) -> Select<'a> {
+        Select {
+            selector: self.clone(),
+            stack: vec![(vec![node], 0)],
+            started: false,
+        }
+    }
+}
+
+impl Node {
+    /// Returns this node and all nodes inside it which match a CSS selector, in document order
+    /// See Selector::select
+    pub fn select(&self, selector: &str) -> Result<Select<'_>, Error> {
+        Ok(Selector::new(selector)?.select(self))
+    }
+
+    /// Returns the first node which matches a CSS selector, or None if there is none
+    pub fn select_first(&self, selector: &str) -> Result<Option<&Node>, Error> {
+        Ok(self.select(selector)?.next())
+    }
+}
+
+/// An iterator over the nodes which match a selector, returned by Node::select
+pub struct Select<'a> {
+    selector: Selector,
+    /// The path to the current node, where every level holds the node and its siblings
+    stack: Vec<(Vec<&'a Node>, usize)>,
+    started: bool,
+}
+
+impl<'a> Select<'a> {
+    /// Moves to the next node in document order, returning false at the end
+    fn advance(&mut self) -> bool {
+        if !self.started {
+            self.started = true;
+            return true;
+        }
+
+        let current = match self.stack.last() {
+            Some((siblings, i)) => siblings[*i],
+            None => return false,
+        };
+        let children: Vec<_> = current.children().collect();
+        if !children.is_empty() {
+            self.stack.push((children, 0));
+            return true;
+        }
+
+        while let Some((siblings, i)) = self.stack.last_mut() {
+            *i += 1;
+            if *i < siblings.len() {
+                return true;
+            }
+            self.stack.pop();
+        }
+        false
+    }
+
+    /// Returns true if the compounds of complex up to and including i match the node at index
+    /// among the siblings at depth, following the combinators from right to left
+    fn matches(&self, complex: &Complex, i: usize, depth: usize, index: usize) -> bool {
+        if !complex.compounds[i].matches(&self.stack[depth].0, index, depth) {
+            return false;
+        }
+        if i == 0 {
+            return true;
+        }
+
+        match complex.combinators[i - 1] {
+            Combinator::Child => {
+                depth > 0 && self.matches(complex, i - 1, depth - 1, self.stack[depth - 1].1)
+            }
+            Combinator::Descendant => (0..depth)
+                .rev()
+                .any(|d| self.matches(complex, i - 1, d, self.stack[d].1)),
+            Combinator::Adjacent => index > 0 && self.matches(complex, i - 1, depth, index - 1),
+            Combinator::Sibling => (0..index)
+                .rev()
+                .any(|j| self.matches(complex, i - 1, depth, j)),
+        }
+    }
+}
+
+impl<'a> Iterator for Select<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.advance() {
+            let depth = self.stack.len() - 1;
+            let (siblings, index) = &self.stack[depth];
+            let (node, index) = (siblings[*index], *index);
+            let found =
+                self.selector.complex.iter().any(|complex| {
+                    self.matches(complex, complex.compounds.len() - 1, depth, index)
+                });
+            if found {
+                return Some(node);
+            }
+        }
+        None
+    }
+}
+
+impl Compound {
+    fn matches(&self, siblings: &[&Node], index: usize, depth: usize) -> bool {
+        let node = siblings[index];
+        if self.tag.as_ref().is_some_and(|tag| *tag != node.tag) {
+            return false;
+        }
+        self.attributes
+            .iter()
+            .all(|attribute| attribute.matches(node))
+            && self
+                .pseudo
+                .iter()
+                .all(|pseudo| pseudo.matches(siblings, index, depth))
+    }
+}
+
+impl Attribute {
+    fn matches(&self, node: &Node) -> bool {
+        let value = match node.get_attribute(&self.key) {
+            Some(v) => v.as_str(),
+            None => return false,
+        };
+        let expected = self.value.as_str();
+
+        match self.op {
+            Op::Exists => true,
+            Op::Equals => value == expected,
+            Op::Word => value.split_whitespace().any(|word| word == expected),
+            Op::Dash => {
+                value == expected
+                    || value
+                        .strip_prefix(expected)
+                        .is_some_and(|rest| rest.starts_with('-'))
+            }
+            // An empty value never matches these
+            Op::Prefix => !expected.is_empty() && value.starts_with(expected),
+            Op::Suffix => !expected.is_empty() && value.ends_with(expected),
+            Op::Substring => !expected.is_empty() && value.contains(expected),
+        }
+    }
+}
+
+impl Pseudo {
+    fn matches(&self, siblings: &[&Node], index: usize, depth: usize) -> bool {
+        let node = siblings[index];
+        // The position among the counted siblings, starting at 1, and their number
+        let count = |of_type: bool| {
+            let counted = |sibling: &&&Node| !of_type || sibling.tag == node.tag;
+            let before = siblings[..index].iter().filter(counted).count();
+            let total = siblings.iter().filter(counted).count();
+            (before as i64 + 1, total as i64)
+        };
+
+        match *self {
+            Pseudo::Nth {
+                a,
+                b,
+                of_type,
+                last,
+            } => {
+                let (position, total) = count(of_type);
+                let position = match last {
+                    true => total - position + 1,
+                    false => position,
+                };
+                match a {
+                    0 => position == b,
+                    _ => (position - b) % a == 0 && (position - b) / a >= 0,
+                }
+            }
+            Pseudo::Only { of_type } => count(of_type).1 == 1,
+            Pseudo::Empty => node.items().all(|item| matches!(item, Item::Comment(_))),
+            Pseudo::Root => depth == 0,
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '\\') || !c.is_ascii()
+}
+
+struct Parser<'s> {
+    input: &'s str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.unexpected(&format!("'{}'", c))),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(c) => format!("expected {} but found '{}'", expected, c),
+            None => format!("expected {} at the end", expected),
+        }
+    }
+
+    /// Skips whitespace, returning true if there was any
+    fn whitespace(&mut self) -> bool {
+        let rest = &self.input[self.pos..];
+        let skipped = rest.len() - rest.trim_start().len();
+        self.pos += skipped;
+        skipped > 0
+    }
+
+    /// Reads a name, where a backslash escapes the next character
+    fn ident(&mut self) -> Result<String, String> {
+        let mut ident = String::new();
+        let mut chars = self.input[self.pos..].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(escaped) => {
+                        ident.push(escaped);
+                        self.pos += 1 + escaped.len_utf8();
+                    }
+                    None => return Err("escape at the end".to_owned()),
+                },
+                c if is_ident_char(c) => {
+                    ident.push(c);
+                    self.pos += c.len_utf8();
+                }
+                _ => break,
+            }
+        }
+
+        match ident.is_empty() {
+            true => Err(self.unexpected("a name")),
+            false => Ok(ident),
+        }
+    }
+
+    fn list(&mut self) -> Result<Vec<Complex>, String> {
+        let mut list = Vec::new();
+        loop {
+            self.whitespace();
+            list.push(self.complex()?);
+            if !self.eat(',') {
+                break;
+            }
+        }
+        match self.peek() {
+            Some(_) => Err(self.unexpected("a combinator or ','")),
+            None => Ok(list),
+        }
+    }
+
+    fn complex(&mut self) -> Result<Complex, String> {
+        let mut complex = Complex {
+            compounds: vec![self.compound()?],
+            combinators: Vec::new(),
+        };
+
+        loop {
+            let whitespace = self.whitespace();
+            let combinator = match self.peek() {
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::Adjacent,
+                Some('~') => Combinator::Sibling,
+                Some(',') | None => return Ok(complex),
+                Some(_) if whitespace => Combinator::Descendant,
+                Some(_) => return Ok(complex),
+            };
+            if combinator != Combinator::Descendant {
+                self.pos += 1;
+                self.whitespace();
+            }
+            complex.combinators.push(combinator);
+            complex.compounds.push(self.compound()?);
+        }
+    }
+
+    fn compound(&mut self) -> Result<Compound, String> {
+        let mut compound = Compound::default();
+        let start = self.pos;
+        match self.peek() {
+            Some('*') => self.pos += 1,
+            Some(c) if is_ident_char(c) => compound.tag = Some(self.ident()?),
+            _ => {}
+        }
+
+        loop {
+            match self.peek() {
+                Some('[') => {
+                    self.pos += 1;
+                    compound.attributes.push(self.attribute()?);
+                }
+                Some('#') => {
+                    self.pos += 1;
+                    compound.attributes.push(Attribute {
+                        key: "id".to_owned(),
+                        op: Op::Equals,
+                        value: self.ident()?,
+                    });
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    compound.attributes.push(Attribute {
+                        key: "class".to_owned(),
+                        op: Op::Word,
+                        value: self.ident()?,
+                    });
+                }
+                Some(':') => {
+                    self.pos += 1;
+                    compound.pseudo.push(self.pseudo()?);
+                }
+                _ => break,
+            }
+        }
+
+        match self.pos == start {
+            true => Err(self.unexpected("a selector")),
+            false => Ok(compound),
+        }
+    }
+
+    /// Parses an attribute selector after its [
+    fn attribute(&mut self) -> Result<Attribute, String> {
+        self.whitespace();
+        let key = self.ident()?;
+        self.whitespace();
+
+        let op = match self.peek() {
+            Some(']') => {
+                self.pos += 1;
+                return Ok(Attribute {
+                    key,
+                    op: Op::Exists,
+                    value: String::new(),
+                });
+            }
+            Some('=') => Op::Equals,
+            Some('~') => Op::Word,
+            Some('|') => Op::Dash,
+            Some('^') => Op::Prefix,
+            Some('$') => Op::Suffix,
+            Some('*') => Op::Substring,
+            _ => return Err(self.unexpected("an attribute operator")),
+        };
+        self.pos += 1;
+        if op != Op::Equals {
+            self.expect('=')?;
+        }
+        self.whitespace();
+
+        let value = match self.peek() {
+            Some(quote) if quote == '"' || quote == '\'' => {
+                let rest = &self.input[self.pos + 1..];
+                let end = rest.find(quote).ok_or("unterminated string")?;
+                self.pos += end + 2;
+                rest[..end].to_owned()
+            }
+            _ => self.ident()?,
+        };
+        self.whitespace();
+        self.expect(']')?;
+        Ok(Attribute { key, op, value })
+    }
+
+    /// Parses a pseudo-class after its :
+    fn pseudo(&mut self) -> Result<Pseudo, String> {
+        let name = self.ident()?;
+        let nth = |a, b, of_type, last| Pseudo::Nth {
+            a,
+            b,
+            of_type,
+            last,
+        };
+        Ok(match name.as_str() {
+            "first-child" => nth(0, 1, false, false),
+            "last-child" => nth(0, 1, false, true),
+            "first-of-type" => nth(0, 1, true, false),
+            "last-of-type" => nth(0, 1, true, true),
+            "only-child" => Pseudo::Only { of_type: false },
+            "only-of-type" => Pseudo::Only { of_type: true },
+            "empty" => Pseudo::Empty,
+            "root" => Pseudo::Root,
+            "nth-child" | "nth-last-child" | "nth-of-type" | "nth-last-of-type" => {
+                self.expect('(')?;
+                let end = self.input[self.pos..].find(')').ok_or("missing ')'")?;
+                let (a, b) = parse_nth(&self.input[self.pos..self.pos + end])?;
+                self.pos += end + 1;
+                nth(
+                    a,
+                    b,
+                    name.ends_with("of-type"),
+                    name.starts_with("nth-last"),
+                )
+            }
+            _ => return Err(format!("unsupported pseudo-class :{}", name)),
+        })
+    }
+}
+
+/// Parses the an+b argument of the :nth- pseudo-classes, including odd and even
+fn parse_nth(argument: &str) -> Result<(i64, i64), String> {
+    let argument: String = argument.split_whitespace().collect();
+    let invalid = || format!("invalid argument \"{}\"", argument);
+    let number = |v: &str| v.parse::<i64>().map_err(|_| invalid());
+
+    match argument.as_str() {
+        "odd" => return Ok((2, 1)),
+        "even" => return Ok((2, 0)),
+        _ => {}
+    }
+    match argument.split_once('n') {
+        Some((a, b)) => {
+            let a = match a {
+                "" | "+" => 1,
+                "-" => -1,
+                a => number(a)?,
+            };
+            let b = match b {
+                "" => 0,
+                b if b.starts_with('+') || b.starts_with('-') => number(b)?,
+                _ => return Err(invalid()),
+            };
+            Ok((a, b))
+        }
+        None => Ok((0, number(&argument)?)),
+    }
+}
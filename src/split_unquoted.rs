@@ -1,11 +1,12 @@
 //! This is a module providing the functionality to split a string with a delimiter function unless the delimiter is surrounded in quotes
-//! The quotes can also be escaped
+//! Both double quotes and apostrophes are quotes, and each can appear unquoted inside the other
 
 pub struct SplitUnquoted<'a, F>
 where
     F: Fn(char) -> bool,
 {
-    in_quotes: bool,
+    /// The quote character of the currently open quote
+    quote: Option<char>,
     data: &'a str,
     del: F,
 }
@@ -16,7 +17,7 @@ where
 {
     pub fn split(data: &'a str, delimiter_func: F) -> Self {
         SplitUnquoted {
-            in_quotes: false,
+            quote: None,
             data,
             del: delimiter_func,
         }
@@ -31,15 +32,17 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         let mut non_del = false;
         for (i, c) in self.data.char_indices() {
-            if c == '"' {
-                self.in_quotes = !self.in_quotes;
+            match self.quote {
+                Some(q) if q == c => self.quote = None,
+                None if c == '"' || c == '\'' => self.quote = Some(c),
+                _ => {}
             }
 
             if (self.del)(c) {
-                if non_del && !self.in_quotes {
+                if non_del && self.quote.is_none() {
                     let end = &self.data[..i];
                     // println!("Iter end: '{}', i: {}", end, i);
-                    self.data = &self.data[i + 1..];
+                    self.data = &self.data[i + c.len_utf8()..];
                     // println!("data: '{}'",&self.data[i..]);
                     return Some(end);
                 }
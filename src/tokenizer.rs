@@ -0,0 +1,311 @@
+//! This is a module providing the tokenizer shared by all parsers
+//! It splits xml into markup and text in a single pass without decoding anything, so the parsers only
+//! differ in what they do with the tokens
+//! The input may be incomplete, in which case a token that could continue is not returned until
+//! more input is available
+
+use crate::split_unquoted::SplitUnquoted;
+use crate::ParseError;
+
+/// A piece of xml as it appears in the input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Token<'a> {
+    /// Text between markup, neither trimmed nor decoded
+    Text(&'a str),
+    /// An opening or empty tag with its attributes left unparsed
+    StartTag {
+        name: &'a str,
+        attributes: &'a str,
+        empty: bool,
+    },
+    EndTag(&'a str),
+    Comment(&'a str),
+    CData(&'a str),
+    /// A processing instruction without the surrounding <? and ?>
+    ProcessingInstruction(&'a str),
+    /// A document type declaration without the surrounding <!DOCTYPE and >, trimmed
+    Doctype(&'a str),
+}
+
+/// The result of scanning for the next token
+pub(crate) enum Scan<'a> {
+    /// A token and the number of bytes it takes up at the start of the input
+    /// If the token is malformed, the error is set and the token is what could be recovered from it
+    Token(Token<'a>, usize, Option<ParseError>),
+    /// More input is needed to finish the token
+    Incomplete,
+    /// There is no input left
+    End,
+}
+
+/// The markup that starts with a fixed prefix, tested before tags
+const PREFIXES: [&str; 5] = ["<!--", "<![CDATA[", "<!DOCTYPE", "<?", "</"];
+
+/// Returns true if c may start a tag name
+pub(crate) fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == ':'
+}
+
+/// Scans the token at the start of input
+/// If eof is false more input may follow, so a token which might continue is not returned
+/// At the end of the input, unterminated markup is returned along with an error
+pub(crate) fn scan(input: &str, eof: bool) -> Scan<'_> {
+    if input.is_empty() {
+        return match eof {
+            true => Scan::End,
+            false => Scan::Incomplete,
+        };
+    }
+
+    if !input.starts_with('<') {
+        return match input.find('<') {
+            Some(i) => Scan::Token(Token::Text(&input[..i]), i, None),
+            None if eof => Scan::Token(Token::Text(input), input.len(), None),
+            None => Scan::Incomplete,
+        };
+    }
+
+    // Not enough input to tell what kind of markup this is
+    if !eof
+        && PREFIXES
+            .iter()
+            .any(|p| p.len() > input.len() && p.starts_with(input))
+    {
+        return Scan::Incomplete;
+    }
+
+    if input.starts_with("<!--") {
+        return delimited(
+            input,
+            4,
+            "-->",
+            eof,
+            Token::Comment,
+            ParseError::MissingCommentEnd,
+        );
+    }
+    if input.starts_with("<![CDATA[") {
+        return delimited(
+            input,
+            9,
+            "]]>",
+            eof,
+            Token::CData,
+            ParseError::MissingCDataEnd,
+        );
+    }
+    if input.starts_with("<?") {
+        return delimited(
+            input,
+            2,
+            "?>",
+            eof,
+            Token::ProcessingInstruction,
+            ParseError::MissingClosingDelimiter,
+        );
+    }
+    if input.starts_with("<!DOCTYPE") {
+        return doctype(input, eof);
+    }
+
+    let is_closing = input.starts_with("</");
+    let name = &input[if is_closing { 2 } else { 1 }..];
+    match name.chars().next() {
+        None if !eof => return Scan::Incomplete,
+        None => return stray(input, ParseError::InvalidTagName(String::new())),
+        Some(c) if !is_name_start(c) => {
+            let end = name
+                .find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '<')
+                .unwrap_or(name.len());
+            return stray(input, ParseError::InvalidTagName(name[..end].to_owned()));
+        }
+        Some(_) => {}
+    }
+
+    // '>' may appear in quoted attribute values
+    // If the quotes are unbalanced, fall back to the first '>' so the attribute is reported instead
+    let end = match find_tag_end(input) {
+        Some(v) => v,
+        None if !eof => return Scan::Incomplete,
+        None => match input.find('>') {
+            Some(v) => v,
+            None => return stray(input, ParseError::MissingClosingDelimiter),
+        },
+    };
+
+    if is_closing {
+        return Scan::Token(Token::EndTag(input[2..end].trim()), end + 1, None);
+    }
+
+    let inner = &input[1..end];
+    let empty = inner.ends_with('/');
+    let inner = if empty {
+        &inner[..inner.len() - 1]
+    } else {
+        inner
+    };
+    let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+    Scan::Token(
+        Token::StartTag {
+            name: &inner[..name_end],
+            attributes: &inner[name_end..],
+            empty,
+        },
+        end + 1,
+        None,
+    )
+}
+
+/// A '<' which does not start any markup is returned as text
+fn stray(input: &str, error: ParseError) -> Scan<'_> {
+    Scan::Token(Token::Text(&input[..1]), 1, Some(error))
+}
+
+/// Scans markup which starts with start_len bytes and ends with end, such as a comment
+fn delimited<'a>(
+    input: &'a str,
+    start_len: usize,
+    end: &str,
+    eof: bool,
+    token: fn(&'a str) -> Token<'a>,
+    error: ParseError,
+) -> Scan<'a> {
+    let body = &input[start_len..];
+    match body.find(end) {
+        Some(i) => Scan::Token(token(&body[..i]), start_len + i + end.len(), None),
+        None if eof => Scan::Token(token(body), input.len(), Some(error)),
+        None => Scan::Incomplete,
+    }
+}
+
+/// Scans a document type declaration
+/// The internal subset in square brackets may contain '>'
+fn doctype(input: &str, eof: bool) -> Scan<'_> {
+    let body = &input[9..];
+    let mut depth = 0;
+    let mut quote = None;
+    let end = body.bytes().position(|c| {
+        match (quote, c) {
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, b'"') | (None, b'\'') => quote = Some(c),
+            (None, b'[') => depth += 1,
+            (None, b']') => depth -= 1,
+            (None, b'>') if depth == 0 => return true,
+            _ => {}
+        }
+        false
+    });
+
+    match end {
+        Some(i) => Scan::Token(Token::Doctype(body[..i].trim()), 9 + i + 1, None),
+        None if eof => Scan::Token(
+            Token::Doctype(body.trim()),
+            input.len(),
+            Some(ParseError::MissingClosingDelimiter),
+        ),
+        None => Scan::Incomplete,
+    }
+}
+
+/// Returns the index of the '>' ending the tag at the start of input, skipping quoted values
+fn find_tag_end(input: &str) -> Option<usize> {
+    let mut quote = None;
+    input.bytes().position(|c| {
+        match (quote, c) {
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, b'"') | (None, b'\'') => quote = Some(c),
+            (None, b'>') => return true,
+            _ => {}
+        }
+        false
+    })
+}
+
+/// An attribute as it appears in a start tag
+pub(crate) struct RawAttribute<'a> {
+    /// The whole attribute, such as key="value", used to position errors
+    pub part: &'a str,
+    pub key: &'a str,
+    /// The value without quotes and not decoded
+    pub value: &'a str,
+    /// Set if the attribute is malformed
+    /// An attribute without a value should be skipped, while a value with missing quotes or
+    /// whitespace after it is kept with whatever quotes it has removed
+    pub error: Option<ParseError>,
+}
+
+/// Splits the attributes of a start tag
+pub(crate) fn attributes(raw: &str) -> Vec<RawAttribute<'_>> {
+    let mut result = Vec::new();
+    let mut parts = SplitUnquoted::split(raw, |c| c.is_whitespace()).peekable();
+    // What follows an attribute which is not separated from it by whitespace
+    let mut rest = None;
+    while let Some(part) = rest.take().or_else(|| parts.next()) {
+        let mut part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+
+        // Whitespace is allowed around the equal sign, which splits the attribute into several parts
+        // Join them back together as a slice of the raw attributes
+        while (!part.contains('=') && parts.peek().is_some_and(|p| p.starts_with('=')))
+            || part.ends_with('=')
+        {
+            let next = match parts.next() {
+                Some(v) => v,
+                None => break,
+            };
+            let start = part.as_ptr() as usize - raw.as_ptr() as usize;
+            let end = next.as_ptr() as usize - raw.as_ptr() as usize + next.len();
+            part = raw[start..end].trim();
+        }
+
+        let equal_sign = match part.find('=') {
+            Some(v) => v,
+            None => {
+                result.push(RawAttribute {
+                    part,
+                    key: part,
+                    value: &part[part.len()..],
+                    error: Some(ParseError::MissingAttributeValue(part.to_owned())),
+                });
+                continue;
+            }
+        };
+
+        let (key, value) = part.split_at(equal_sign);
+        let (key, value) = (key.trim(), value[1..].trim_start());
+
+        // Values can be quoted with either double quotes or apostrophes
+        // The closing quote has to be followed by whitespace or the end of the tag
+        let closing = match value.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => value[1..].find(quote).map(|i| i + 1),
+            _ => None,
+        };
+        let (value, error) = match closing {
+            Some(i) if i + 1 == value.len() => (&value[1..i], None),
+            Some(i) => {
+                rest = Some(&value[i + 1..]);
+                part = &part[..part.len() - value.len() + i + 1];
+                (
+                    &value[1..i],
+                    Some(ParseError::MissingWhitespace(part.to_owned())),
+                )
+            }
+            None => (
+                value.trim_matches(|c| c == '"' || c == '\''),
+                Some(ParseError::MissingQuotes(part.to_owned())),
+            ),
+        };
+
+        result.push(RawAttribute {
+            part,
+            key,
+            value,
+            error,
+        });
+    }
+    result
+}
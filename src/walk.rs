@@ -0,0 +1,153 @@
+//! This is a module providing iterators which walk every node inside a node
+//! Nodes are visited with an explicit stack or queue
+//! ```
+//! let cube = szl_simple_xml::from_file("examples/cube.dae").unwrap();
+//! let sources: Vec<_> = cube.descendants_with_tag("source").collect();
+//! assert_eq!(sources.len(), 3);
+//!
+//! let mut descendants = cube.descendants();
+//! while let Some(node) = descendants.next() {
+//!     if node.tag == "xfov" {
+//!         assert_eq!(descendants.depth(), 6);
+//!     }
+//! }
+//! ```
+
+use crate::Node;
+use std::collections::VecDeque;
+
+/// Iterates the nodes inside a node depth-first, visiting every node before its children
+pub struct Descendants<'a> {
+    /// The remaining children of every node on the path to the current node
+    stack: Vec<std::vec::IntoIter<&'a Node>>,
+    depth: usize,
+}
+
+impl Descendants<'_> {
+    /// Returns the depth of the node returned last, where the children of the starting node are at 1
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(node) => {
+                    self.depth = self.stack.len();
+                    self.stack
+                        .push(node.children().collect::<Vec<_>>().into_iter());
+                    return Some(node);
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Iterates the nodes inside a node breadth-first, visiting all nodes at one depth before the next
+pub struct BreadthFirst<'a> {
+    queue: VecDeque<(&'a Node, usize)>,
+    depth: usize,
+}
+
+impl BreadthFirst<'_> {
+    /// Returns the depth of the node returned last, where the children of the starting node are at 1
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl<'a> Iterator for BreadthFirst<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, depth) = self.queue.pop_front()?;
+        self.depth = depth;
+        self.queue
+            .extend(node.children().map(|child| (child, depth + 1)));
+        Some(node)
+    }
+}
+
+impl Node {
+    /// Returns every node inside this node depth-first in document order, not including this node
+    pub fn descendants(&self) -> Descendants<'_> {
+        Descendants {
+            stack: vec![self.children().collect::<Vec<_>>().into_iter()],
+            depth: 0,
+        }
+    }
+
+    /// Returns every node inside this node breadth-first, not including this node
+    pub fn descendants_breadth_first(&self) -> BreadthFirst<'_> {
+        BreadthFirst {
+            queue: self.children().map(|child| (child, 1)).collect(),
+            depth: 0,
+        }
+    }
+
+    /// Returns every node inside this node with the given tag, depth-first in document order
+    pub fn descendants_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Node> {
+        self.descendants().filter(move |node| node.tag == tag)
+    }
+
+    /// Returns the first node inside this node for which predicate is true, searching depth-first
+    pub fn find<P: FnMut(&Node) -> bool>(&self, mut predicate: P) -> Option<&Node> {
+        self.descendants().find(|node| predicate(node))
+    }
+
+    /// Returns every node inside this node for which predicate is true, depth-first in document order
+    pub fn find_all<'a, P: FnMut(&Node) -> bool + 'a>(
+        &'a self,
+        mut predicate: P,
+    ) -> impl Iterator<Item = &'a Node> {
+        self.descendants().filter(move |node| predicate(node))
+    }
+
+    /// Returns the first node inside this node for which predicate is true mutably, searching
+    /// depth-first
+    pub fn find_mut<P: FnMut(&Node) -> bool>(&mut self, predicate: P) -> Option<&mut Node> {
+        self.find_all_mut(predicate).next()
+    }
+
+    /// Returns the nodes inside this node for which predicate is true mutably, depth-first in
+    /// document order
+    /// As a node can only be borrowed mutably once, the nodes inside a match are not searched
+    pub fn find_all_mut<P: FnMut(&Node) -> bool>(
+        &mut self,
+        mut predicate: P,
+    ) -> impl Iterator<Item = &mut Node> {
+        let mut found = Vec::new();
+        let mut stack: Vec<&mut Node> = self.children_mut().collect();
+        stack.reverse();
+        while let Some(node) = stack.pop() {
+            if predicate(node) {
+                found.push(node);
+            } else {
+                let start = stack.len();
+                stack.extend(node.children_mut());
+                stack[start..].reverse();
+            }
+        }
+        found.into_iter()
+    }
+
+    /// Calls f with every node inside this node and its depth, depth-first in document order
+    /// Children added or removed by f are walked accordingly
+    pub fn walk_mut<F: FnMut(&mut Node, usize)>(&mut self, mut f: F) {
+        let mut stack: Vec<(&mut Node, usize)> = self.children_mut().map(|n| (n, 1)).collect();
+        stack.reverse();
+        while let Some((node, depth)) = stack.pop() {
+            f(node, depth);
+            let start = stack.len();
+            stack.extend(node.children_mut().map(|child| (child, depth + 1)));
+            stack[start..].reverse();
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! This is a module providing the functionality to write a node tree back into xml
+//! The output can be tweaked with WriteOptions
+
+use crate::escape;
+use crate::{Item, Node};
+
+/// Options controlling how xml is written
+/// The default options match the behaviour of to_string and save_to_file
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WriteOptions {
+    /// Put every node on its own line and indent it with 4 spaces per level
+    pub pretty: bool,
+    /// Write content containing markup characters ('<', '>' or '&') as a CDATA section
+    pub cdata: bool,
+    /// Write content and attribute values as they are, without escaping markup characters
+    pub raw: bool,
+    /// Write attributes sorted alphabetically by key instead of in insertion order
+    pub sort_attributes: bool,
+}
+
+/// A node whose closing tag has not been written yet
+struct Open<'a> {
+    node: &'a Node,
+    items: std::vec::IntoIter<Item<'a>>,
+    options: WriteOptions,
+    depth: usize,
+    /// Whether every item is put on its own line
+    lines: bool,
+}
+
+/// Writes a node and all its children into out
+/// Open nodes are kept on an explicit stack, like when parsing
+pub(crate) fn write_node(node: &Node, options: &WriteOptions, depth: usize, out: &mut String) {
+    let mut stack = Vec::new();
+    write_open(node, *options, depth, out, &mut stack);
+
+    while let Some(open) = stack.last_mut() {
+        let item = match open.items.next() {
+            Some(v) => v,
+            None => {
+                let open = stack.pop().unwrap();
+                if open.lines {
+                    out.push_str(&" ".repeat(open.depth * 4));
+                }
+                out.push_str(&format!("</{}>", open.node.tag));
+                if open.options.pretty {
+                    out.push('\n');
+                }
+                continue;
+            }
+        };
+
+        let (lines, depth) = (open.lines, open.depth);
+        let options = WriteOptions {
+            pretty: lines,
+            ..open.options
+        };
+        if lines && !matches!(item, Item::Node(_)) {
+            out.push_str(&" ".repeat(depth * 4 + 4));
+        }
+
+        match item {
+            Item::Node(child) => write_open(child, options, depth + 1, out, &mut stack),
+            Item::Text(text) => write_content(text, &options, out),
+            Item::CData(data) => write_cdata(data, out),
+            Item::Comment(comment) => {
+                out.push_str(&format!("<!--{}-->", escape::escape_comment(comment)))
+            }
+        }
+
+        if lines && !matches!(item, Item::Node(_)) {
+            out.push('\n');
+        }
+    }
+}
+
+/// Writes the opening tag of a node, and pushes the node onto stack unless it is empty
+fn write_open<'a>(
+    node: &'a Node,
+    options: WriteOptions,
+    depth: usize,
+    out: &mut String,
+    stack: &mut Vec<Open<'a>>,
+) {
+    if node.tag.is_empty() {
+        return;
+    }
+
+    if options.pretty {
+        out.push_str(&" ".repeat(depth * 4));
+    }
+    out.push('<');
+    out.push_str(&node.tag);
+    let mut attributes: Vec<_> = node.attributes.iter().collect();
+    if options.sort_attributes {
+        attributes.sort_by_key(|(k, _)| *k);
+    }
+
+    for (k, v) in attributes {
+        let v = match options.raw {
+            true => v.into(),
+            false => escape::escape_attribute(v),
+        };
+        out.push_str(&format!(" {}=\"{}\"", k, v));
+    }
+
+    let items: Vec<_> = node.items().collect();
+    if items.is_empty() {
+        out.push_str("/>");
+        if options.pretty {
+            out.push('\n');
+        }
+        return;
+    }
+
+    out.push('>');
+
+    // Nodes with text are kept on one line, as whitespace added around the text would become part of it
+    // Otherwise every item is put on its own line
+    let has_text = items
+        .iter()
+        .any(|item| matches!(item, Item::Text(_) | Item::CData(_)));
+    let lines = options.pretty && !has_text;
+    if lines {
+        out.push('\n');
+    }
+
+    stack.push(Open {
+        node,
+        items: items.into_iter(),
+        options,
+        depth,
+        lines,
+    });
+}
+
+/// Writes the text content of a node, as CDATA if requested and needed
+fn write_content(content: &str, options: &WriteOptions, out: &mut String) {
+    if options.cdata && content.contains(['<', '>', '&']) {
+        write_cdata(content, out);
+    } else if options.raw {
+        out.push_str(content);
+    } else {
+        out.push_str(&escape::escape_text(content));
+    }
+}
+
+/// Writes data as a CDATA section
+fn write_cdata(data: &str, out: &mut String) {
+    // A CDATA section can not contain its own terminator, so it is split in two
+    out.push_str("<![CDATA[");
+    out.push_str(&data.replace("]]>", "]]]]><![CDATA[>"));
+    out.push_str("]]>");
+}
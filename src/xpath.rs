@@ -0,0 +1,1312 @@
+//! This is a module providing an XPath 1.0 evaluator over nodes
+//! Location paths with every axis but namespace, predicates, unions, comparisons, arithmetic and
+//! the core function library are supported, apart from id(), lang() and processing instructions
+//! Prefixes are matched as written in the document, without resolving namespaces
+//! ```
+//! use szl_simple_xml::xpath::Value;
+//!
+//! let graph = szl_simple_xml::from_file("examples/graph.xml").unwrap();
+//! let edges = graph.xpath_nodes("//edge[@from='n3']").unwrap();
+//! assert_eq!(edges.len(), 2);
+//! assert_eq!(edges[0].get_attribute("id").unwrap(), "e3");
+//!
+//! match graph.xpath("count(/graph/node)").unwrap() {
+//!     Value::Number(n) => assert_eq!(n, 4.0),
+//!     v => panic!("Expected a number, got {:?}", v),
+//! }
+//! assert_eq!(graph.xpath("string(node[1]/label)").unwrap().string(), "Start");
+//! ```
+
+use crate::arena::Links;
+use crate::{Error, Item, Node};
+use std::cmp::Ordering;
+
+/// A compiled XPath expression, which can be evaluated against any number of nodes
+#[derive(Debug, Clone)]
+pub struct XPath {
+    expression: String,
+    expr: Expr,
+}
+
+/// The result of evaluating an expression
+#[derive(Debug, Clone)]
+pub enum Value<'a> {
+    /// The selected nodes in document order
+    Nodes(Vec<Match<'a>>),
+    String(String),
+    Number(f64),
+    Boolean(bool),
+}
+
+/// A node selected by an expression
+#[derive(Debug, Clone, Copy)]
+pub enum Match<'a> {
+    /// The root of the document selected by /, whose only child is the node the expression was
+    /// evaluated against
+    Root(&'a Node),
+    Element(&'a Node),
+    /// An attribute with its key and value
+    Attribute(&'a str, &'a str),
+    /// A run of text or a CDATA section
+    Text(&'a str),
+    Comment(&'a str),
+}
+
+impl XPath {
+    /// Compiles an expression
+    /// Fails with InvalidXPath on syntax errors, unsupported features and unknown functions
+    pub fn new(expression: &str) -> Result<Self, Error> {
+        let error = |message: String| Error::InvalidXPath(expression.to_owned(), message);
+        let tokens = lex(expression).map_err(error)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.expr().map_err(error)?;
+        if let Some(token) = parser.peek() {
+            return Err(error(format!("unexpected {:?}", token)));
+        }
+
+        Ok(XPath {
+            expression: expression.to_owned(),
+            expr,
+        })
+    }
+
+    /// Evaluates the expression with node as the context node
+    /// The node is treated as the root node of the document, so absolute paths start above it
+    pub fn evaluate<'a>(&self, node: &'a Node) -> Result<Value<'a>, Error> {
+        let eval = Eval {
+            index: Index::new(node),
+            expression: &self.expression,
+        };
+        let context = Context {
+            node: Ref::element(1),
+            position: 1,
+            size: 1,
+        };
+
+        Ok(match eval.eval(&self.expr, &context)? {
+            Object::Nodes(nodes) => {
+                Value::Nodes(nodes.into_iter().map(|r| eval.index.get(r)).collect())
+            }
+            Object::String(v) => Value::String(v),
+            Object::Number(v) => Value::Number(v),
+            Object::Boolean(v) => Value::Boolean(v),
+        })
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Returns the selected element nodes, which is empty if the value is not a node set
+    pub fn nodes(&self) -> Vec<&'a Node> {
+        match self {
+            Value::Nodes(nodes) => nodes
+                .iter()
+                .filter_map(|m| match m {
+                    Match::Element(node) => Some(*node),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Converts the value to a string like the string() function
+    pub fn string(&self) -> String {
+        match self {
+            Value::Nodes(nodes) => nodes.first().map(Match::string_value).unwrap_or_default(),
+            Value::String(v) => v.clone(),
+            Value::Number(v) => number_to_string(*v),
+            Value::Boolean(v) => v.to_string(),
+        }
+    }
+
+    /// Converts the value to a number like the number() function
+    pub fn number(&self) -> f64 {
+        match self {
+            Value::Number(v) => *v,
+            Value::Boolean(v) => bool_to_number(*v),
+            _ => string_to_number(&self.string()),
+        }
+    }
+
+    /// Converts the value to a boolean like the boolean() function
+    pub fn boolean(&self) -> bool {
+        match self {
+            Value::Nodes(nodes) => !nodes.is_empty(),
+            Value::String(v) => !v.is_empty(),
+            Value::Number(v) => number_to_bool(*v),
+            Value::Boolean(v) => *v,
+        }
+    }
+}
+
+impl Match<'_> {
+    /// Returns the string value of the node, which for the root and elements is all the text inside them
+    pub fn string_value(&self) -> String {
+        match self {
+            Match::Root(node) | Match::Element(node) => text_of(node),
+            Match::Attribute(_, v) | Match::Text(v) | Match::Comment(v) => (*v).to_owned(),
+        }
+    }
+}
+
+impl Node {
+    /// Evaluates an XPath expression with this node as the context node, see XPath::evaluate
+    pub fn xpath(&self, expression: &str) -> Result<Value<'_>, Error> {
+        XPath::new(expression)?.evaluate(self)
+    }
+
+    /// Evaluates an XPath expression and returns the selected element nodes in document order
+    /// Fails with InvalidXPath if the expression returns a string, number or boolean
+    pub fn xpath_nodes(&self, expression: &str) -> Result<Vec<&Node>, Error> {
+        match self.xpath(expression)? {
+            v @ Value::Nodes(_) => Ok(v.nodes()),
+            _ => Err(Error::InvalidXPath(
+                expression.to_owned(),
+                "expression does not return a node set".to_owned(),
+            )),
+        }
+    }
+}
+
+/// Returns all the text inside a node in document order
+fn text_of(node: &Node) -> String {
+    let mut text = String::new();
+    let mut stack = vec![node.items()];
+    while let Some(items) = stack.last_mut() {
+        match items.next() {
+            Some(Item::Node(child)) => stack.push(child.items()),
+            Some(Item::Text(v)) | Some(Item::CData(v)) => text.push_str(v),
+            Some(Item::Comment(_)) => {}
+            None => {
+                stack.pop();
+            }
+        }
+    }
+    text
+}
+
+fn bool_to_number(v: bool) -> f64 {
+    match v {
+        true => 1.0,
+        false => 0.0,
+    }
+}
+
+fn number_to_bool(v: f64) -> bool {
+    v != 0.0 && !v.is_nan()
+}
+
+/// Formats a number the way XPath does, without a fraction for integers and never in exponent notation
+fn number_to_string(v: f64) -> String {
+    if v.is_nan() {
+        "NaN".to_owned()
+    } else if v.is_infinite() {
+        match v > 0.0 {
+            true => "Infinity".to_owned(),
+            false => "-Infinity".to_owned(),
+        }
+    } else if v == v.trunc() && v.abs() < 1e15 {
+        (v as i64).to_string()
+    } else {
+        v.to_string()
+    }
+}
+
+/// Parses a number the way XPath does, which only allows an optional minus, digits and a point
+/// Anything else is NaN
+fn string_to_number(s: &str) -> f64 {
+    let s = s.trim();
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    let valid = digits.chars().any(|c| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1;
+    match valid {
+        true => s.parse().unwrap_or(f64::NAN),
+        false => f64::NAN,
+    }
+}
+
+/// Rounds to the nearest integer, with halves rounded up
+fn round(v: f64) -> f64 {
+    match v.is_finite() {
+        true => (v + 0.5).floor(),
+        false => v,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    DotDot,
+    At,
+    Comma,
+    ColonColon,
+    Slash,
+    DoubleSlash,
+    Pipe,
+    Plus,
+    Minus,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Multiply,
+    And,
+    Or,
+    Div,
+    Mod,
+    /// A name test such as para, * or svg:*, or the name of a function, node type or axis
+    Name(String),
+    Literal(String),
+    Number(f64),
+}
+
+impl Token {
+    /// Returns true if a * or name following this token is an operator, following the rules of
+    /// the XPath specification
+    fn precedes_operator(&self) -> bool {
+        !matches!(
+            self,
+            Token::At
+                | Token::ColonColon
+                | Token::LParen
+                | Token::LBracket
+                | Token::Comma
+                | Token::Slash
+                | Token::DoubleSlash
+                | Token::Pipe
+                | Token::Plus
+                | Token::Minus
+                | Token::Eq
+                | Token::Ne
+                | Token::Lt
+                | Token::Le
+                | Token::Gt
+                | Token::Ge
+                | Token::Multiply
+                | Token::And
+                | Token::Or
+                | Token::Div
+                | Token::Mod
+        )
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// Splits an expression into tokens
+fn lex(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut rest = expression.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let operator = tokens.last().is_some_and(Token::precedes_operator);
+        let two = rest.get(..2).unwrap_or("");
+        let (token, len) = match c {
+            '(' => (Token::LParen, 1),
+            ')' => (Token::RParen, 1),
+            '[' => (Token::LBracket, 1),
+            ']' => (Token::RBracket, 1),
+            '@' => (Token::At, 1),
+            ',' => (Token::Comma, 1),
+            '|' => (Token::Pipe, 1),
+            '+' => (Token::Plus, 1),
+            '-' => (Token::Minus, 1),
+            '=' => (Token::Eq, 1),
+            '*' if operator => (Token::Multiply, 1),
+            '*' => (Token::Name("*".to_owned()), 1),
+            _ if two == "::" => (Token::ColonColon, 2),
+            _ if two == "//" => (Token::DoubleSlash, 2),
+            _ if two == "!=" => (Token::Ne, 2),
+            _ if two == "<=" => (Token::Le, 2),
+            _ if two == ">=" => (Token::Ge, 2),
+            _ if two == ".." => (Token::DotDot, 2),
+            '/' => (Token::Slash, 1),
+            '<' => (Token::Lt, 1),
+            '>' => (Token::Gt, 1),
+            '"' | '\'' => match rest[1..].find(c) {
+                Some(end) => (Token::Literal(rest[1..end + 1].to_owned()), end + 2),
+                None => return Err("unterminated string literal".to_owned()),
+            },
+            '.' | '0'..='9' => {
+                let len = rest
+                    .find(|c: char| !c.is_ascii_digit() && c != '.')
+                    .unwrap_or(rest.len());
+                match &rest[..len] {
+                    "." => (Token::Dot, 1),
+                    number => match number.parse() {
+                        Ok(v) => (Token::Number(v), len),
+                        Err(_) => return Err(format!("invalid number {}", number)),
+                    },
+                }
+            }
+            '$' => return Err("variables are not supported".to_owned()),
+            _ if is_name_start(c) => {
+                let mut len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+                // A qualified name, or a prefix followed by *
+                let after = &rest[len..];
+                if after.starts_with(':') && !after.starts_with("::") {
+                    match after[1..].chars().next() {
+                        Some('*') => len += 2,
+                        Some(c) if is_name_start(c) => {
+                            len += 1 + after[1..]
+                                .find(|c| !is_name_char(c))
+                                .unwrap_or(after.len() - 1)
+                        }
+                        _ => {}
+                    }
+                }
+
+                let name = &rest[..len];
+                let token = match name {
+                    "and" if operator => Token::And,
+                    "or" if operator => Token::Or,
+                    "div" if operator => Token::Div,
+                    "mod" if operator => Token::Mod,
+                    _ => Token::Name(name.to_owned()),
+                };
+                (token, len)
+            }
+            _ => return Err(format!("unexpected character '{}'", c)),
+        };
+
+        tokens.push(token);
+        rest = rest[len..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Axis {
+    Ancestor,
+    AncestorOrSelf,
+    Attribute,
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    Following,
+    FollowingSibling,
+    Parent,
+    Preceding,
+    PrecedingSibling,
+    Self_,
+}
+
+impl Axis {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "ancestor" => Axis::Ancestor,
+            "ancestor-or-self" => Axis::AncestorOrSelf,
+            "attribute" => Axis::Attribute,
+            "child" => Axis::Child,
+            "descendant" => Axis::Descendant,
+            "descendant-or-self" => Axis::DescendantOrSelf,
+            "following" => Axis::Following,
+            "following-sibling" => Axis::FollowingSibling,
+            "parent" => Axis::Parent,
+            "preceding" => Axis::Preceding,
+            "preceding-sibling" => Axis::PrecedingSibling,
+            "self" => Axis::Self_,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Test {
+    /// A name, or * for any name
+    Name(String),
+    /// A prefix followed by :*
+    Prefix(String),
+    Node,
+    Text,
+    Comment,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    axis: Axis,
+    test: Test,
+    predicates: Vec<Expr>,
+}
+
+#[derive(Debug, Clone)]
+enum Start {
+    Context,
+    Root,
+    Expr(Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    /// Returns the operator with its operands swapped, so that a < b becomes b > a
+    fn swap(self) -> Self {
+        match self {
+            Op::Lt => Op::Gt,
+            Op::Le => Op::Ge,
+            Op::Gt => Op::Lt,
+            Op::Ge => Op::Le,
+            op => op,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Arith {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Compare(Op, Box<Expr>, Box<Expr>),
+    Arith(Arith, Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Union(Box<Expr>, Box<Expr>),
+    Path(Start, Vec<Step>),
+    Filter(Box<Expr>, Vec<Expr>),
+    Literal(String),
+    Number(f64),
+    Function(String, Vec<Expr>),
+}
+
+/// Returns the minimum and maximum number of arguments of a function, or None if it doesn't exist
+fn arity(name: &str) -> Option<(usize, usize)> {
+    Some(match name {
+        "last" | "position" | "true" | "false" => (0, 0),
+        "count" | "sum" | "floor" | "ceiling" | "round" | "boolean" | "not" => (1, 1),
+        "name" | "local-name" | "string" | "string-length" | "normalize-space" | "number" => (0, 1),
+        "contains" | "starts-with" | "substring-before" | "substring-after" => (2, 2),
+        "substring" => (2, 3),
+        "translate" => (3, 3),
+        "concat" => (2, usize::MAX),
+        _ => return None,
+    })
+}
+
+/// A recursive descent parser following the grammar of the XPath specification
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    /// Consumes the next token if it is the given one
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        match self.eat(&token) {
+            true => Ok(()),
+            false => Err(match self.peek() {
+                Some(found) => format!("expected {:?} but found {:?}", token, found),
+                None => format!("expected {:?} at the end", token),
+            }),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.equality()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.equality()?));
+        }
+        Ok(expr)
+    }
+
+    fn equality(&mut self) -> Result<Expr, String> {
+        let mut expr = self.relational()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Eq) => Op::Eq,
+                Some(Token::Ne) => Op::Ne,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::Compare(op, Box::new(expr), Box::new(self.relational()?));
+        }
+    }
+
+    fn relational(&mut self) -> Result<Expr, String> {
+        let mut expr = self.additive()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Lt) => Op::Lt,
+                Some(Token::Le) => Op::Le,
+                Some(Token::Gt) => Op::Gt,
+                Some(Token::Ge) => Op::Ge,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::Compare(op, Box::new(expr), Box::new(self.additive()?));
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        let mut expr = self.multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => Arith::Add,
+                Some(Token::Minus) => Arith::Sub,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::Arith(op, Box::new(expr), Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Multiply) => Arith::Mul,
+                Some(Token::Div) => Arith::Div,
+                Some(Token::Mod) => Arith::Mod,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::Arith(op, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.eat(&Token::Minus) {
+            true => Ok(Expr::Neg(Box::new(self.unary()?))),
+            false => self.union(),
+        }
+    }
+
+    fn union(&mut self) -> Result<Expr, String> {
+        let mut expr = self.path()?;
+        while self.eat(&Token::Pipe) {
+            expr = Expr::Union(Box::new(expr), Box::new(self.path()?));
+        }
+        Ok(expr)
+    }
+
+    /// Parses a location path, or a filter expression optionally followed by a relative path
+    fn path(&mut self) -> Result<Expr, String> {
+        let filter = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Literal(_)), _)
+            | (Some(Token::Number(_)), _)
+            | (Some(Token::LParen), _) => true,
+            (Some(Token::Name(name)), Some(Token::LParen)) => !matches!(
+                name.as_str(),
+                "node" | "text" | "comment" | "processing-instruction"
+            ),
+            _ => false,
+        };
+
+        if filter {
+            let expr = self.filter()?;
+            let mut steps = Vec::new();
+            match self.peek() {
+                Some(Token::Slash) | Some(Token::DoubleSlash) => self.relative(&mut steps)?,
+                _ => return Ok(expr),
+            }
+            return Ok(Expr::Path(Start::Expr(Box::new(expr)), steps));
+        }
+
+        let mut steps = Vec::new();
+        match self.peek() {
+            Some(Token::Slash) => {
+                self.pos += 1;
+                // A lone / selects the root
+                if let Some(Token::Dot) | Some(Token::DotDot) | Some(Token::At)
+                | Some(Token::Name(_)) = self.peek()
+                {
+                    self.steps(&mut steps)?;
+                }
+                Ok(Expr::Path(Start::Root, steps))
+            }
+            Some(Token::DoubleSlash) => {
+                self.relative(&mut steps)?;
+                Ok(Expr::Path(Start::Root, steps))
+            }
+            _ => {
+                self.steps(&mut steps)?;
+                Ok(Expr::Path(Start::Context, steps))
+            }
+        }
+    }
+
+    /// Parses the steps following a / or //
+    fn relative(&mut self, steps: &mut Vec<Step>) -> Result<(), String> {
+        match self.peek() {
+            Some(Token::Slash) => self.pos += 1,
+            Some(Token::DoubleSlash) => {
+                self.pos += 1;
+                steps.push(Step {
+                    axis: Axis::DescendantOrSelf,
+                    test: Test::Node,
+                    predicates: Vec::new(),
+                });
+            }
+            _ => return Ok(()),
+        }
+        self.steps(steps)
+    }
+
+    /// Parses steps separated by / or //
+    fn steps(&mut self, steps: &mut Vec<Step>) -> Result<(), String> {
+        steps.push(self.step()?);
+        self.relative(steps)
+    }
+
+    fn step(&mut self) -> Result<Step, String> {
+        let (axis, test) = match self.peek().cloned() {
+            Some(Token::Dot) => {
+                self.pos += 1;
+                (Axis::Self_, Test::Node)
+            }
+            Some(Token::DotDot) => {
+                self.pos += 1;
+                (Axis::Parent, Test::Node)
+            }
+            Some(Token::At) => {
+                self.pos += 1;
+                (Axis::Attribute, self.test()?)
+            }
+            Some(Token::Name(name)) if self.peek_at(1) == Some(&Token::ColonColon) => {
+                let axis = match name.as_str() {
+                    "namespace" => return Err("the namespace axis is not supported".to_owned()),
+                    name => Axis::from_name(name).ok_or(format!("unknown axis {}", name))?,
+                };
+                self.pos += 2;
+                (axis, self.test()?)
+            }
+            _ => (Axis::Child, self.test()?),
+        };
+
+        let mut predicates = Vec::new();
+        while self.eat(&Token::LBracket) {
+            predicates.push(self.expr()?);
+            self.expect(Token::RBracket)?;
+        }
+        Ok(Step {
+            axis,
+            test,
+            predicates,
+        })
+    }
+
+    fn test(&mut self) -> Result<Test, String> {
+        let name = match self.peek() {
+            Some(Token::Name(name)) => name.clone(),
+            Some(token) => return Err(format!("expected a step but found {:?}", token)),
+            None => return Err("expected a step at the end".to_owned()),
+        };
+        self.pos += 1;
+
+        if self.eat(&Token::LParen) {
+            let test = match name.as_str() {
+                "node" => Test::Node,
+                "text" => Test::Text,
+                "comment" => Test::Comment,
+                _ => return Err(format!("unsupported node type test {}()", name)),
+            };
+            self.expect(Token::RParen)?;
+            return Ok(test);
+        }
+
+        Ok(match name.strip_suffix(":*") {
+            Some(prefix) => Test::Prefix(prefix.to_owned()),
+            None => Test::Name(name),
+        })
+    }
+
+    /// Parses a primary expression followed by predicates
+    fn filter(&mut self) -> Result<Expr, String> {
+        let primary = match self.peek().cloned() {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                self.expect(Token::RParen)?;
+                expr
+            }
+            Some(Token::Literal(v)) => {
+                self.pos += 1;
+                Expr::Literal(v)
+            }
+            Some(Token::Number(v)) => {
+                self.pos += 1;
+                Expr::Number(v)
+            }
+            Some(Token::Name(name)) => {
+                self.pos += 2;
+                let mut args = Vec::new();
+                if !self.eat(&Token::RParen) {
+                    loop {
+                        args.push(self.expr()?);
+                        if !self.eat(&Token::Comma) {
+                            break;
+                        }
+                    }
+                    self.expect(Token::RParen)?;
+                }
+
+                let (min, max) = arity(&name).ok_or(format!("unknown function {}()", name))?;
+                if args.len() < min || args.len() > max {
+                    return Err(format!("{}() does not take {} arguments", name, args.len()));
+                }
+                Expr::Function(name, args)
+            }
+            _ => unreachable!("filter is only parsed at a primary expression"),
+        };
+
+        let mut predicates = Vec::new();
+        while self.eat(&Token::LBracket) {
+            predicates.push(self.expr()?);
+            self.expect(Token::RBracket)?;
+        }
+        match predicates.is_empty() {
+            true => Ok(primary),
+            false => Ok(Expr::Filter(Box::new(primary), predicates)),
+        }
+    }
+}
+
+/// A node in the index, where attributes are addressed through their element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Ref {
+    id: usize,
+    /// The index of the attribute of the element
+    attribute: Option<usize>,
+}
+
+impl Ref {
+    fn element(id: usize) -> Self {
+        Ref {
+            id,
+            attribute: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Kind<'a> {
+    Root(&'a Node),
+    Element(&'a Node),
+    Text(&'a str),
+    Comment(&'a str),
+}
+
+/// The nodes of a tree in document order with links to their parents, since Node has none
+/// The root is at 0 and the node the expression is evaluated against at 1
+struct Index<'a> {
+    nodes: Links<Kind<'a>>,
+}
+
+impl<'a> Index<'a> {
+    fn new(node: &'a Node) -> Self {
+        let mut nodes = Links::new();
+        nodes.push(Kind::Root(node), None);
+        nodes.push(Kind::Element(node), Some(0));
+
+        let mut stack = vec![(1, node.items())];
+        while let Some((id, items)) = stack.last_mut() {
+            let id = *id;
+            let kind = match items.next() {
+                Some(Item::Node(child)) => {
+                    let child_id = nodes.push(Kind::Element(child), Some(id));
+                    stack.push((child_id, child.items()));
+                    continue;
+                }
+                Some(Item::Text(v)) | Some(Item::CData(v)) => Kind::Text(v),
+                Some(Item::Comment(v)) => Kind::Comment(v),
+                None => {
+                    nodes.close(id);
+                    stack.pop();
+                    continue;
+                }
+            };
+            nodes.push(kind, Some(id));
+        }
+        nodes.close(0);
+        Index { nodes }
+    }
+
+    fn get(&self, r: Ref) -> Match<'a> {
+        match (self.nodes.get(r.id), r.attribute) {
+            (Kind::Element(node), Some(i)) => {
+                let (k, v) = node.attributes.iter().nth(i).unwrap();
+                Match::Attribute(k, v)
+            }
+            (Kind::Root(node), _) => Match::Root(node),
+            (Kind::Element(node), _) => Match::Element(node),
+            (Kind::Text(v), _) => Match::Text(v),
+            (Kind::Comment(v), _) => Match::Comment(v),
+        }
+    }
+
+    /// Returns the name of an element or attribute, or an empty string for other nodes
+    fn name(&self, r: Ref) -> &'a str {
+        match self.get(r) {
+            Match::Element(node) => &node.tag,
+            Match::Attribute(k, _) => k,
+            _ => "",
+        }
+    }
+
+    fn string_value(&self, r: Ref) -> String {
+        match self.get(r) {
+            Match::Root(_) | Match::Element(_) => (r.id..self.nodes.end(r.id))
+                .filter_map(|id| match self.nodes.get(id) {
+                    Kind::Text(v) => Some(*v),
+                    _ => None,
+                })
+                .collect(),
+            m => m.string_value(),
+        }
+    }
+
+    /// Returns the nodes along an axis in proximity order, which is reversed for the backward axes
+    fn axis(&self, axis: Axis, r: Ref) -> Vec<Ref> {
+        let end = self.nodes.end(r.id);
+        match axis {
+            Axis::Self_ => vec![r],
+            Axis::Ancestor | Axis::AncestorOrSelf => {
+                let mut nodes = match axis {
+                    Axis::AncestorOrSelf => vec![r],
+                    _ => Vec::new(),
+                };
+                if r.attribute.is_some() {
+                    nodes.push(Ref::element(r.id));
+                }
+                nodes.extend(self.nodes.ancestors(r.id).map(Ref::element));
+                nodes
+            }
+            Axis::Parent => match r.attribute {
+                Some(_) => vec![Ref::element(r.id)],
+                None => self
+                    .nodes
+                    .parent(r.id)
+                    .map(Ref::element)
+                    .into_iter()
+                    .collect(),
+            },
+            // Attributes have no children
+            _ if r.attribute.is_some() && axis != Axis::Following && axis != Axis::Preceding => {
+                match axis {
+                    Axis::DescendantOrSelf => vec![r],
+                    _ => Vec::new(),
+                }
+            }
+            Axis::Attribute => match self.nodes.get(r.id) {
+                Kind::Element(node) => (0..node.attributes.len())
+                    .map(|i| Ref {
+                        id: r.id,
+                        attribute: Some(i),
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            },
+            Axis::Child => self
+                .nodes
+                .children(r.id)
+                .iter()
+                .copied()
+                .map(Ref::element)
+                .collect(),
+            Axis::Descendant => (r.id + 1..end).map(Ref::element).collect(),
+            Axis::DescendantOrSelf => (r.id..end).map(Ref::element).collect(),
+            Axis::FollowingSibling => self
+                .nodes
+                .following_siblings(r.id)
+                .iter()
+                .copied()
+                .map(Ref::element)
+                .collect(),
+            Axis::PrecedingSibling => self
+                .nodes
+                .preceding_siblings(r.id)
+                .iter()
+                .rev()
+                .copied()
+                .map(Ref::element)
+                .collect(),
+            Axis::Following => {
+                // The children of an element follow its attributes
+                let start = match r.attribute {
+                    Some(_) => r.id + 1,
+                    None => end,
+                };
+                (start..self.nodes.len()).map(Ref::element).collect()
+            }
+            Axis::Preceding => (1..r.id)
+                .rev()
+                .filter(|&id| self.nodes.end(id) <= r.id)
+                .map(Ref::element)
+                .collect(),
+        }
+    }
+
+    fn matches(&self, step: &Step, r: Ref) -> bool {
+        let kind = self.nodes.get(r.id);
+        let principal = match step.axis {
+            Axis::Attribute => r.attribute.is_some(),
+            _ => r.attribute.is_none() && matches!(kind, Kind::Element(_)),
+        };
+
+        match &step.test {
+            Test::Name(name) if name == "*" => principal,
+            Test::Name(name) => principal && self.name(r) == name,
+            Test::Prefix(prefix) => {
+                principal
+                    && self
+                        .name(r)
+                        .split_once(':')
+                        .is_some_and(|(p, _)| p == prefix)
+            }
+            Test::Node => true,
+            Test::Text => r.attribute.is_none() && matches!(kind, Kind::Text(_)),
+            Test::Comment => r.attribute.is_none() && matches!(kind, Kind::Comment(_)),
+        }
+    }
+}
+
+/// A value during evaluation, where nodes are still references into the index
+#[derive(Debug, Clone)]
+enum Object {
+    Nodes(Vec<Ref>),
+    String(String),
+    Number(f64),
+    Boolean(bool),
+}
+
+struct Context {
+    node: Ref,
+    position: usize,
+    size: usize,
+}
+
+struct Eval<'a, 'e> {
+    index: Index<'a>,
+    expression: &'e str,
+}
+
+impl Eval<'_, '_> {
+    fn error(&self, message: &str) -> Error {
+        Error::InvalidXPath(self.expression.to_owned(), message.to_owned())
+    }
+
+    fn string(&self, object: &Object) -> String {
+        match object {
+            Object::Nodes(nodes) => nodes
+                .first()
+                .map(|&r| self.index.string_value(r))
+                .unwrap_or_default(),
+            Object::String(v) => v.clone(),
+            Object::Number(v) => number_to_string(*v),
+            Object::Boolean(v) => v.to_string(),
+        }
+    }
+
+    fn number(&self, object: &Object) -> f64 {
+        match object {
+            Object::Number(v) => *v,
+            Object::Boolean(v) => bool_to_number(*v),
+            _ => string_to_number(&self.string(object)),
+        }
+    }
+
+    fn boolean(&self, object: &Object) -> bool {
+        match object {
+            Object::Nodes(nodes) => !nodes.is_empty(),
+            Object::String(v) => !v.is_empty(),
+            Object::Number(v) => number_to_bool(*v),
+            Object::Boolean(v) => *v,
+        }
+    }
+
+    fn nodes(&self, object: Object, what: &str) -> Result<Vec<Ref>, Error> {
+        match object {
+            Object::Nodes(nodes) => Ok(nodes),
+            _ => Err(self.error(&format!("{} is not a node set", what))),
+        }
+    }
+
+    fn eval(&self, expr: &Expr, context: &Context) -> Result<Object, Error> {
+        Ok(match expr {
+            Expr::Or(a, b) => Object::Boolean(
+                self.boolean(&self.eval(a, context)?) || self.boolean(&self.eval(b, context)?),
+            ),
+            Expr::And(a, b) => Object::Boolean(
+                self.boolean(&self.eval(a, context)?) && self.boolean(&self.eval(b, context)?),
+            ),
+            Expr::Compare(op, a, b) => {
+                let (a, b) = (self.eval(a, context)?, self.eval(b, context)?);
+                Object::Boolean(self.compare(*op, &a, &b))
+            }
+            Expr::Arith(op, a, b) => {
+                let a = self.number(&self.eval(a, context)?);
+                let b = self.number(&self.eval(b, context)?);
+                Object::Number(match op {
+                    Arith::Add => a + b,
+                    Arith::Sub => a - b,
+                    Arith::Mul => a * b,
+                    Arith::Div => a / b,
+                    Arith::Mod => a % b,
+                })
+            }
+            Expr::Neg(a) => Object::Number(-self.number(&self.eval(a, context)?)),
+            Expr::Union(a, b) => {
+                let mut nodes = self.nodes(self.eval(a, context)?, "operand of |")?;
+                nodes.extend(self.nodes(self.eval(b, context)?, "operand of |")?);
+                nodes.sort();
+                nodes.dedup();
+                Object::Nodes(nodes)
+            }
+            Expr::Path(start, steps) => {
+                let mut nodes = match start {
+                    Start::Context => vec![context.node],
+                    Start::Root => vec![Ref::element(0)],
+                    Start::Expr(expr) => self.nodes(self.eval(expr, context)?, "start of path")?,
+                };
+                for step in steps {
+                    nodes = self.step(step, &nodes)?;
+                }
+                Object::Nodes(nodes)
+            }
+            Expr::Filter(expr, predicates) => {
+                let mut nodes = self.nodes(self.eval(expr, context)?, "filtered expression")?;
+                for predicate in predicates {
+                    nodes = self.filter(nodes, predicate)?;
+                }
+                Object::Nodes(nodes)
+            }
+            Expr::Literal(v) => Object::String(v.clone()),
+            Expr::Number(v) => Object::Number(*v),
+            Expr::Function(name, args) => self.call(name, args, context)?,
+        })
+    }
+
+    /// Selects the nodes of a step from every context node, returning them in document order
+    fn step(&self, step: &Step, nodes: &[Ref]) -> Result<Vec<Ref>, Error> {
+        let mut result = Vec::new();
+        for &node in nodes {
+            let mut selected: Vec<_> = self
+                .index
+                .axis(step.axis, node)
+                .into_iter()
+                .filter(|&r| self.index.matches(step, r))
+                .collect();
+            for predicate in &step.predicates {
+                selected = self.filter(selected, predicate)?;
+            }
+            result.extend(selected);
+        }
+        result.sort();
+        result.dedup();
+        Ok(result)
+    }
+
+    /// Keeps the nodes for which the predicate is true
+    /// A number is true at that position, counting from 1
+    fn filter(&self, nodes: Vec<Ref>, predicate: &Expr) -> Result<Vec<Ref>, Error> {
+        let size = nodes.len();
+        let mut result = Vec::new();
+        for (i, node) in nodes.into_iter().enumerate() {
+            let context = Context {
+                node,
+                position: i + 1,
+                size,
+            };
+            let keep = match self.eval(predicate, &context)? {
+                Object::Number(v) => v == (i + 1) as f64,
+                v => self.boolean(&v),
+            };
+            if keep {
+                result.push(node);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Compares two values, where a node set compares true if any of its nodes does
+    fn compare(&self, op: Op, a: &Object, b: &Object) -> bool {
+        match (a, b) {
+            (Object::Nodes(a), Object::Nodes(b)) => a.iter().any(|&x| {
+                let x = Object::String(self.index.string_value(x));
+                b.iter().any(|&y| {
+                    self.compare_atoms(op, &x, &Object::String(self.index.string_value(y)))
+                })
+            }),
+            (Object::Nodes(a), Object::Boolean(_)) => {
+                self.compare_atoms(op, &Object::Boolean(!a.is_empty()), b)
+            }
+            (Object::Nodes(a), _) => a
+                .iter()
+                .any(|&x| self.compare_atoms(op, &Object::String(self.index.string_value(x)), b)),
+            (_, Object::Nodes(_)) => self.compare(op.swap(), b, a),
+            _ => self.compare_atoms(op, a, b),
+        }
+    }
+
+    /// Compares two values which are not node sets
+    fn compare_atoms(&self, op: Op, a: &Object, b: &Object) -> bool {
+        let ordering = match op {
+            Op::Eq | Op::Ne => {
+                let equal = match (a, b) {
+                    (Object::Boolean(_), _) | (_, Object::Boolean(_)) => {
+                        self.boolean(a) == self.boolean(b)
+                    }
+                    (Object::Number(_), _) | (_, Object::Number(_)) => {
+                        self.number(a) == self.number(b)
+                    }
+                    _ => self.string(a) == self.string(b),
+                };
+                return equal == (op == Op::Eq);
+            }
+            _ => self.number(a).partial_cmp(&self.number(b)),
+        };
+
+        match ordering {
+            Some(Ordering::Less) => matches!(op, Op::Lt | Op::Le),
+            Some(Ordering::Equal) => matches!(op, Op::Le | Op::Ge),
+            Some(Ordering::Greater) => matches!(op, Op::Gt | Op::Ge),
+            None => false,
+        }
+    }
+
+    fn call(&self, name: &str, args: &[Expr], context: &Context) -> Result<Object, Error> {
+        let values = args
+            .iter()
+            .map(|arg| self.eval(arg, context))
+            .collect::<Result<Vec<_>, _>>()?;
+        // The context node is used when the optional argument is missing
+        let arg = |i: usize| match values.get(i) {
+            Some(v) => v.clone(),
+            None => Object::Nodes(vec![context.node]),
+        };
+        let string = |i: usize| self.string(&arg(i));
+        let number = |i: usize| self.number(&arg(i));
+
+        Ok(match name {
+            "last" => Object::Number(context.size as f64),
+            "position" => Object::Number(context.position as f64),
+            "count" => Object::Number(self.nodes(arg(0), "argument of count()")?.len() as f64),
+            "name" | "local-name" => {
+                let nodes = self.nodes(arg(0), &format!("argument of {}()", name))?;
+                let full = nodes.first().map_or("", |&r| self.index.name(r));
+                Object::String(match (name, full.split_once(':')) {
+                    ("local-name", Some((_, local))) => local.to_owned(),
+                    _ => full.to_owned(),
+                })
+            }
+            "string" => Object::String(string(0)),
+            "concat" => Object::String((0..values.len()).map(string).collect()),
+            "starts-with" => Object::Boolean(string(0).starts_with(&string(1))),
+            "contains" => Object::Boolean(string(0).contains(&string(1))),
+            "substring-before" => {
+                let (s, pattern) = (string(0), string(1));
+                Object::String(s.find(&pattern).map_or("", |i| &s[..i]).to_owned())
+            }
+            "substring-after" => {
+                let (s, pattern) = (string(0), string(1));
+                Object::String(
+                    s.find(&pattern)
+                        .map_or("", |i| &s[i + pattern.len()..])
+                        .to_owned(),
+                )
+            }
+            "substring" => {
+                let start = round(number(1));
+                let end = match values.len() {
+                    3 => start + round(number(2)),
+                    _ => f64::INFINITY,
+                };
+                Object::String(
+                    string(0)
+                        .chars()
+                        .enumerate()
+                        .filter(|(i, _)| {
+                            let position = (i + 1) as f64;
+                            position >= start && position < end
+                        })
+                        .map(|(_, c)| c)
+                        .collect(),
+                )
+            }
+            "string-length" => Object::Number(string(0).chars().count() as f64),
+            "normalize-space" => {
+                Object::String(string(0).split_whitespace().collect::<Vec<_>>().join(" "))
+            }
+            "translate" => {
+                let from: Vec<char> = string(1).chars().collect();
+                let to: Vec<char> = string(2).chars().collect();
+                Object::String(
+                    string(0)
+                        .chars()
+                        .filter_map(|c| match from.iter().position(|&f| f == c) {
+                            Some(i) => to.get(i).copied(),
+                            None => Some(c),
+                        })
+                        .collect(),
+                )
+            }
+            "boolean" => Object::Boolean(self.boolean(&arg(0))),
+            "not" => Object::Boolean(!self.boolean(&arg(0))),
+            "true" => Object::Boolean(true),
+            "false" => Object::Boolean(false),
+            "number" => Object::Number(number(0)),
+            "sum" => Object::Number(
+                self.nodes(arg(0), "argument of sum()")?
+                    .into_iter()
+                    .map(|r| string_to_number(&self.index.string_value(r)))
+                    .sum(),
+            ),
+            "floor" => Object::Number(number(0).floor()),
+            "ceiling" => Object::Number(number(0).ceil()),
+            "round" => Object::Number(round(number(0))),
+            _ => unreachable!("functions are checked when compiling"),
+        })
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{self, assert_deep, deep_xml};
+    use szl_simple_xml::arena::Arena;
+    use szl_simple_xml::{Node, ParseOptions};
+
+    #[test]
+    fn arena_navigation() {
+        let root = szl_simple_xml::from_file("./examples/cube.dae").expect("Failed to parse cube");
+        let arena = Arena::from(&root);
+        assert_eq!(arena[arena.root()].tag, "COLLADA");
+        assert_eq!(arena.parent(arena.root()), None);
+
+        let sources: Vec<_> = arena
+            .descendants(arena.root())
+            .filter(|&id| arena[id].tag == "source")
+            .collect();
+        assert_eq!(sources.len(), 3);
+
+        let geometry = arena
+            .ancestors(sources[1])
+            .find(|&id| arena[id].tag == "geometry")
+            .expect("Missing enclosing geometry");
+        assert_eq!(arena[geometry].get_attribute("id").unwrap(), "Cube-mesh");
+        assert_eq!(arena.ancestors(sources[1]).last(), Some(arena.root()));
+
+        assert_eq!(arena.prev_sibling(sources[1]), Some(sources[0]));
+        assert_eq!(arena.next_sibling(sources[1]), Some(sources[2]));
+        assert_eq!(arena.prev_sibling(sources[0]), None);
+        assert_eq!(arena.prev_sibling(arena.root()), None);
+        assert_eq!(arena.next_sibling(arena.root()), None);
+
+        let mesh = arena.parent(sources[0]).unwrap();
+        assert_eq!(arena[mesh].tag, "mesh");
+        assert_eq!(arena.children(mesh).take(3).collect::<Vec<_>>(), sources);
+        assert!(arena
+            .children(sources[0])
+            .all(|id| arena.parent(id) == Some(sources[0])));
+    }
+
+    #[test]
+    fn arena_round_trip() {
+        let options = ParseOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        common::for_each_example(|file, xml| {
+            let root =
+                szl_simple_xml::from_string_with(xml, options).expect("Failed to parse example");
+            let arena = Arena::from(&root);
+            assert_eq!(Node::from(&arena).to_string(), root.to_string(), "{}", file);
+        });
+
+        let root = szl_simple_xml::from_string("<a>one<b>two</b>three<!-- x --></a>").unwrap();
+        let mut arena = Arena::from(&root);
+        let b = arena.children(arena.root()).next().unwrap();
+        assert_eq!(arena.to_node(b).to_string(), "<b>two</b>");
+
+        arena[b].content = "changed".to_owned();
+        arena[b].attributes.insert("x".to_owned(), "1".to_owned());
+        assert_eq!(
+            Node::from(&arena).to_string(),
+            "<a>one<b x=\"1\">changed</b>three</a>"
+        );
+    }
+
+    #[test]
+    fn arena_deep_nesting() {
+        let depth = 100_000;
+        let xml = deep_xml(depth);
+        let root = szl_simple_xml::from_string(&xml).expect("Failed to parse deep nesting");
+
+        let arena = Arena::from(&root);
+        let leaf = arena.descendants(arena.root()).last().unwrap();
+        assert_eq!(arena[leaf].content, "leaf");
+        assert_eq!(arena.ancestors(leaf).count(), depth - 1);
+
+        assert_deep(&Node::from(&arena), depth);
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use szl_simple_xml::async_io::AsyncReader;
+    use szl_simple_xml::reader::Event;
+    use szl_simple_xml::{Document, WriteOptions};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Writes data into one end of a small duplex stream, a few bytes at a time
+    async fn send(mut writer: tokio::io::DuplexStream, data: &[u8]) {
+        for chunk in data.chunks(7) {
+            writer.write_all(chunk).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn parse_async_reader() {
+        let xml = std::fs::read_to_string("./examples/cube.dae").unwrap();
+        let (writer, reader) = tokio::io::duplex(64);
+        let (_, root) = tokio::join!(
+            send(writer, xml.as_bytes()),
+            szl_simple_xml::from_async_reader(reader)
+        );
+        let root = root.expect("Failed to parse cube");
+        assert_eq!(
+            root.to_string(),
+            szl_simple_xml::from_string(&xml).unwrap().to_string()
+        );
+
+        let (writer, reader) = tokio::io::duplex(64);
+        let (_, e) = tokio::join!(
+            send(writer, b"<a><b></a>"),
+            szl_simple_xml::from_async_reader(reader)
+        );
+        assert_eq!(
+            e.unwrap_err().kind(),
+            szl_simple_xml::ErrorKind::MissingClosingTag
+        );
+
+        // Documents are built from the events the same way as by from_string
+        let xml = "<?xml version=\"1.0\" standalone=\"yes\"?>\n<!DOCTYPE p>\n<!-- before -->\
+            <p>\n  Hello <b>big</b> world<![CDATA[ & ]]><?skipped?>\n</p><?after x?>";
+        let options = szl_simple_xml::ParseOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        let (writer, reader) = tokio::io::duplex(16);
+        let (_, document) = tokio::join!(
+            send(writer, xml.as_bytes()),
+            Document::from_async_reader_with(reader, options)
+        );
+        let document = document.expect("Failed to parse document");
+        let expected = Document::from_string_with(xml, options).unwrap();
+        assert_eq!(document.to_string(), expected.to_string());
+        assert_eq!(document.standalone, Some(true));
+        assert_eq!(document.root.content, "Hello  world & ");
+
+        // A token longer than a read is read in growing pieces
+        let xml = format!("<a>{}</a>", "x".repeat(1 << 20));
+        let root = szl_simple_xml::from_async_reader(xml.as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(root.content.len(), 1 << 20);
+    }
+
+    #[tokio::test]
+    async fn read_async_events() {
+        let xml = "<?xml version=\"1.0\"?><stream><message>héllo</message><presence/></stream>";
+        let (writer, reader) = tokio::io::duplex(16);
+        let read = async {
+            let mut reader = AsyncReader::new(reader);
+            let mut events = Vec::new();
+            loop {
+                match reader.next_event().await.expect("Failed to read event") {
+                    Event::Eof => break,
+                    event => events.push(event),
+                }
+            }
+            events
+        };
+        let (_, events) = tokio::join!(send(writer, xml.as_bytes()), read);
+
+        let expected: Vec<_> = szl_simple_xml::reader::Reader::new(xml.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(events, expected);
+        assert_eq!(events[3], Event::Text("héllo".to_owned()));
+
+        // A '<' at the end of the input is an error rather than waiting for more
+        for xml in ["<a/><", "<a/></"] {
+            let mut reader = AsyncReader::new(xml.as_bytes());
+            let mut events = Vec::new();
+            let e = loop {
+                match reader.next_event().await {
+                    Ok(event) => events.push(event),
+                    Err(e) => break e,
+                }
+            };
+            assert_eq!(e.kind(), szl_simple_xml::ErrorKind::InvalidTagName);
+            assert_eq!(events.len(), 2);
+            assert_eq!(reader.next_event().await.unwrap(), Event::Eof);
+        }
+    }
+
+    #[tokio::test]
+    async fn save_async() {
+        let document = Document::from_file("./examples/mutable.xml").unwrap();
+        let options = WriteOptions {
+            pretty: true,
+            ..Default::default()
+        };
+
+        let (mut writer, mut reader) = tokio::io::duplex(1 << 16);
+        document.write_async(&mut writer, options).await.unwrap();
+        drop(writer);
+        let mut written = String::new();
+        reader.read_to_string(&mut written).await.unwrap();
+        assert_eq!(written, document.to_string_with(options));
+
+        let path = std::env::temp_dir().join("szl_simple_xml_save_async.xml");
+        document
+            .root
+            .save_to_file_pretty_async(&path)
+            .await
+            .unwrap();
+        let saved = szl_simple_xml::from_file(&path).unwrap();
+        assert_eq!(saved.to_string(), document.root.to_string());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{self, assert_deep, deep_xml};
+    use std::borrow::Cow;
+    use szl_simple_xml::{BorrowedItem, ParseOptions};
+
+    #[test]
+    fn borrowed_matches_from_string() {
+        let options = ParseOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        common::for_each_example(|file, xml| {
+            let expected = szl_simple_xml::from_string_with(xml, options).unwrap();
+            let borrowed = szl_simple_xml::from_string_borrowed_with(xml, options).unwrap();
+            assert_eq!(
+                borrowed.to_owned().to_string(),
+                expected.to_string(),
+                "{}",
+                file
+            );
+        });
+
+        let inputs = ["<a>\n  <b>\n</a>", "<a b=c/>", "<a>&nope;</a>", "<a/><b/>"];
+        for xml in inputs.iter() {
+            let expected = szl_simple_xml::from_string(xml).unwrap_err();
+            let e = szl_simple_xml::from_string_borrowed(xml).unwrap_err();
+            assert_eq!(e.to_string(), expected.to_string(), "{:?}", xml);
+        }
+    }
+
+    #[test]
+    fn borrowed_references_input() {
+        let xml = "<a x='plain' y='1 &amp; 2'>text<b>more &lt;</b><![CDATA[<raw>]]></a>";
+        let root = szl_simple_xml::from_string_borrowed(xml).expect("Failed to parse borrowed");
+        let range = xml.as_bytes().as_ptr_range();
+
+        assert!(range.contains(&root.tag.as_ptr()));
+        assert!(matches!(&root.attributes[0].1, Cow::Borrowed("plain")));
+        assert!(matches!(&root.attributes[1].1, Cow::Owned(v) if v == "1 & 2"));
+        match &root.items[0] {
+            BorrowedItem::Text(Cow::Borrowed(text)) => {
+                assert!(range.contains(&text.as_ptr()));
+                assert_eq!(*text, "text");
+            }
+            item => panic!("Expected borrowed text, got {:?}", item),
+        }
+        assert_eq!(root.items[2], BorrowedItem::CData("<raw>"));
+        assert_eq!(root.content(), "text<raw>");
+
+        let b = root.get_nodes("b")[0];
+        assert!(matches!(&b.items[0], BorrowedItem::Text(Cow::Owned(v)) if v == "more <"));
+        assert!(root.get_nodes("c").is_empty());
+
+        let raw = ParseOptions {
+            raw: true,
+            ..Default::default()
+        };
+        let root = szl_simple_xml::from_string_borrowed_with(xml, raw).unwrap();
+        assert!(matches!(
+            root.get_nodes("b")[0].content(),
+            Cow::Borrowed("more &lt;")
+        ));
+    }
+
+    #[test]
+    fn borrowed_deep_nesting() {
+        let depth = 100_000;
+        let xml = deep_xml(depth);
+        let root =
+            szl_simple_xml::from_string_borrowed(&xml).expect("Failed to parse deep nesting");
+
+        assert_deep(&root.to_owned(), depth);
+
+        let copy = root.clone();
+        assert!(copy == root);
+        assert!(format!("{:?}", copy).ends_with("children: [\"a\"] }"));
+
+        let other = deep_xml(depth).replace("leaf", "other");
+        let other = szl_simple_xml::from_string_borrowed(&other).unwrap();
+        assert!(other != root);
+    }
+}
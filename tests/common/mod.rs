@@ -0,0 +1,40 @@
+//! Fixtures shared by the integration tests
+// Every test crate includes this module but only uses some of it
+#![allow(dead_code)]
+
+use szl_simple_xml::Node;
+
+/// The example files which every representation should read the same way as from_string
+pub const EXAMPLES: [&str; 6] = [
+    "cube.dae",
+    "graph.xml",
+    "message.xml",
+    "mutable.xml",
+    "note.xml",
+    "person.xml",
+];
+
+/// Calls check with the name and contents of every example file
+pub fn for_each_example(mut check: impl FnMut(&str, &str)) {
+    for file in EXAMPLES.iter() {
+        let xml = std::fs::read_to_string(format!("./examples/{}", file)).unwrap();
+        check(file, &xml);
+    }
+}
+
+/// Returns depth nested <a> nodes with the text leaf in the innermost one
+pub fn deep_xml(depth: usize) -> String {
+    "<a>".repeat(depth) + "leaf" + &"</a>".repeat(depth)
+}
+
+/// Checks that root is the tree of deep_xml(depth)
+pub fn assert_deep(root: &Node, depth: usize) {
+    let mut node = root;
+    let mut found = 1;
+    while let Some(child) = node["a"].first() {
+        node = child;
+        found += 1;
+    }
+    assert_eq!(found, depth);
+    assert_eq!(node.content, "leaf");
+}
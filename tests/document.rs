@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use szl_simple_xml::{Document, Misc};
+
+    #[test]
+    fn keep_declaration() {
+        let mut document =
+            Document::from_file("./examples/mutable.xml").expect("Failed to parse mutable.xml");
+        assert_eq!(document.version.as_deref(), Some("1.0"));
+        assert_eq!(document.encoding.as_deref(), Some("utf-8"));
+        assert_eq!(document.standalone, None);
+
+        let resource = &mut document.get_mut_nodes("resources").unwrap()[0]
+            .get_mut_nodes("resource")
+            .unwrap()[0];
+        let mut file = szl_simple_xml::new("file", String::new());
+        file.add_attribute("href", "page1.html");
+        resource.add_node(file);
+
+        let written = document.to_string_pretty();
+        assert!(written.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<manifest>\n"));
+
+        let read = Document::from_string(&written).expect("Failed to read back document");
+        assert_eq!(read.encoding.as_deref(), Some("utf-8"));
+        assert_eq!(read["resources"][0]["resource"][0]["file"].len(), 4);
+
+        // A bare node has no declaration
+        assert!(szl_simple_xml::from_file("./examples/mutable.xml")
+            .unwrap()
+            .to_string()
+            .starts_with("<manifest>"));
+    }
+
+    #[test]
+    fn keep_prolog() {
+        let xml = "\u{feff}<?xml version='1.1' standalone=\"yes\"?>
+<!DOCTYPE note [
+  <!ELEMENT note (#PCDATA)>
+  <!ENTITY writer \"Donald > Duck\">
+]>
+<!-- written by hand -->
+<?xml-stylesheet href=\"style.css\"?>
+<note>Hi</note>
+<?done?>
+";
+        let options = szl_simple_xml::ParseOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        let document = Document::from_string_with(xml, options).expect("Failed to parse");
+        assert_eq!(document.version.as_deref(), Some("1.1"));
+        assert_eq!(document.standalone, Some(true));
+        assert!(document.doctype.as_ref().unwrap().starts_with("note ["));
+        assert!(document.doctype.as_ref().unwrap().ends_with("Duck\">\n]"));
+        assert_eq!(
+            document.prolog,
+            [
+                Misc::Comment(" written by hand ".to_owned()),
+                Misc::ProcessingInstruction(
+                    "xml-stylesheet".to_owned(),
+                    "href=\"style.css\"".to_owned()
+                )
+            ]
+        );
+        assert_eq!(document.root.content, "Hi");
+        assert_eq!(
+            document.epilog,
+            [Misc::ProcessingInstruction(
+                "done".to_owned(),
+                String::new()
+            )]
+        );
+
+        let written = document.to_string();
+        assert!(written.starts_with("<?xml version=\"1.1\" standalone=\"yes\"?>\n<!DOCTYPE note ["));
+        let read = Document::from_string_with(&written, options).unwrap();
+        assert_eq!(read.doctype, document.doctype);
+        assert_eq!(read.prolog, document.prolog);
+        assert_eq!(read.epilog, document.epilog);
+        assert_eq!(read.to_string(), written);
+
+        // The declaration is split like attributes, with whitespace allowed around the equal sign
+        let document = Document::from_string("<?xml version = \"1.0\" encoding= 'utf-8' ?><a/>")
+            .expect("Failed to parse spaced declaration");
+        assert_eq!(document.version.as_deref(), Some("1.0"));
+        assert_eq!(document.encoding.as_deref(), Some("utf-8"));
+        let declarations = [
+            "<?xml version=\"1.0'?><a/>",
+            "<?xml version=1.0?><a/>",
+            "<?xml version?><a/>",
+        ];
+        for xml in declarations.iter() {
+            match Document::from_string(xml) {
+                Err(szl_simple_xml::Error::ParseError(
+                    szl_simple_xml::ParseError::MissingQuotes(_),
+                    _,
+                ))
+                | Err(szl_simple_xml::Error::ParseError(
+                    szl_simple_xml::ParseError::MissingAttributeValue(_),
+                    _,
+                )) => {}
+                v => panic!("Expected an invalid declaration for {:?}, got {:?}", xml, v),
+            }
+        }
+
+        // The declaration may only appear at the start
+        let misplaced = [
+            "<a/><?xml version='1.0'?>",
+            " <!-- x --><?xml version='1.0'?><a/>",
+        ];
+        for xml in misplaced.iter() {
+            match Document::from_string(xml) {
+                Err(szl_simple_xml::Error::ParseError(
+                    szl_simple_xml::ParseError::InvalidDeclaration(attr),
+                    _,
+                )) => assert!(attr.is_empty()),
+                v => panic!(
+                    "Expected a misplaced declaration for {:?}, got {:?}",
+                    xml, v
+                ),
+            }
+        }
+        let (document, errors) =
+            Document::from_string_recovering("<a/><?xml version='1.0'?>", Default::default());
+        assert_eq!(
+            errors[0].kind(),
+            szl_simple_xml::ErrorKind::InvalidDeclaration
+        );
+        assert!(document.epilog.is_empty());
+        assert_eq!(document.to_string(), "<a/>");
+
+        // Only misc items may follow the root
+        match Document::from_string("<a/><b/>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::ContentOutsideRoot,
+                position,
+            )) => assert_eq!(position.offset, 4),
+            v => panic!("Expected ContentOutsideRoot, got {:?}", v),
+        }
+    }
+}
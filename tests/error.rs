@@ -0,0 +1,195 @@
+#[cfg(test)]
+mod tests {
+    use szl_simple_xml::ErrorKind;
+
+    fn load(xml: &str) -> Result<szl_simple_xml::Node, Box<dyn std::error::Error>> {
+        Ok(szl_simple_xml::from_string(xml)?)
+    }
+
+    #[test]
+    fn display_errors() {
+        let xml =
+            "<COLLADA>\n  <library_geometries>\n    <mesh>\n  </library_geometries>\n</COLLADA>";
+        let e = szl_simple_xml::from_string(xml).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::MissingClosingTag);
+        assert_eq!(e.kind().as_str(), "missing-closing-tag");
+        assert_eq!(
+            e.to_string(),
+            "line 3, column 5: missing closing tag </mesh> (in COLLADA/library_geometries/mesh)"
+        );
+        assert_eq!(e.position().unwrap().line, 3);
+
+        // Usable as a boxed error
+        let e = load("<a b=c/>").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "line 1, column 4: attribute value is not quoted in \"b=c\" (in a)"
+        );
+
+        let e = szl_simple_xml::from_string("<a/>\n  text").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::ContentOutsideRoot);
+        assert_eq!(
+            e.to_string(),
+            "line 2, column 3: content outside of the root node"
+        );
+
+        let note = szl_simple_xml::from_file("./examples/note.xml").unwrap();
+        assert_eq!(
+            note.try_get_nodes("missing").unwrap_err().to_string(),
+            "node <note> has no child <missing>"
+        );
+        assert_eq!(
+            note.try_get_attribute("missing").unwrap_err().to_string(),
+            "node <note> has no attribute \"missing\""
+        );
+    }
+
+    #[test]
+    fn io_error_source() {
+        use std::error::Error;
+
+        let e = szl_simple_xml::from_file("./examples/does_not_exist.xml").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::IO);
+        assert!(e.to_string().starts_with("io error: "));
+
+        let source = e.source().expect("Missing io error source");
+        let io = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn render_report() {
+        let xml =
+            "<COLLADA>\n  <library_geometries>\n    <mesh>\n  </library_geometries>\n</COLLADA>";
+        let e = szl_simple_xml::from_string(xml).unwrap_err();
+        assert_eq!(
+            szl_simple_xml::report::render(xml, &e, false),
+            "error[missing-closing-tag]: missing closing tag </mesh>
+ --> line 3, column 5 (in COLLADA/library_geometries/mesh)
+  |
+2 |   <library_geometries>
+3 |     <mesh>
+  |     ^^^^^^
+"
+        );
+
+        let xml = "<a>\n\t<b c=d/>\n</a>";
+        let e = szl_simple_xml::from_string(xml).unwrap_err();
+        let report = szl_simple_xml::report::render(xml, &e, false);
+        assert!(report.ends_with("2 | \t<b c=d/>\n  | \t   ^^^\n"));
+
+        let colored = szl_simple_xml::report::render(xml, &e, true);
+        assert!(colored.contains("\x1b[1;31m^^^\x1b[0m"));
+
+        let e = szl_simple_xml::from_file("./examples/does_not_exist.xml").unwrap_err();
+        let report = szl_simple_xml::report::render("", &e, false);
+        assert!(report.starts_with("error[io]: io error: "));
+        assert_eq!(report.lines().count(), 1);
+
+        // Positions outside the source only render the location
+        let e = szl_simple_xml::Error::ParseError(
+            szl_simple_xml::ParseError::MissingClosingDelimiter,
+            szl_simple_xml::Position::default(),
+        );
+        let report = szl_simple_xml::report::render("<a/>", &e, false);
+        assert!(report.ends_with(" --> line 0, column 0\n  |\n"));
+        let e = szl_simple_xml::Error::ParseError(
+            szl_simple_xml::ParseError::MissingClosingDelimiter,
+            szl_simple_xml::Position {
+                line: 3,
+                column: 9,
+                offset: 4,
+                path: String::new(),
+            },
+        );
+        let report = szl_simple_xml::report::render("<a>\n</a>", &e, false);
+        assert!(report.ends_with(" --> line 3, column 9\n  |\n"));
+
+        let xml = "<a/>junk";
+        let e = szl_simple_xml::from_string(xml).unwrap_err();
+        let report = szl_simple_xml::report::render(xml, &e, false);
+        assert!(
+            report.ends_with("1 | <a/>junk\n  |     ^^^^\n"),
+            "{}",
+            report
+        );
+
+        let xml = "<a b=/>";
+        let e = szl_simple_xml::from_string(xml).unwrap_err();
+        let report = szl_simple_xml::report::render(xml, &e, false);
+        assert!(
+            report.ends_with(" |\n1 | <a b=/>\n  |    ^^\n"),
+            "{}",
+            report
+        );
+    }
+
+    #[test]
+    fn recover_errors() {
+        let xml = "<root>
+  <a href=x>one</a>
+  <b>text < more</b>
+  <c><d>unclosed</c>
+  </e>
+  <f bad>two &nope; &amp;</f>
+</root>";
+        let (root, errors) = szl_simple_xml::from_string_recovering(xml, Default::default());
+
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| {
+                let position = e.position().unwrap();
+                (e.kind(), position.line, position.path.as_str())
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (ErrorKind::MissingQuotes, 2, "root/a"),
+                (ErrorKind::InvalidTagName, 3, "root/b"),
+                (ErrorKind::MissingClosingTag, 4, "root/c/d"),
+                (ErrorKind::UnexpectedClosingTag, 5, "root"),
+                (ErrorKind::MissingAttributeValue, 6, "root/f"),
+                (ErrorKind::InvalidEntity, 6, "root/f"),
+            ]
+        );
+
+        // The first error is the one returned without recovering
+        let e = szl_simple_xml::from_string(xml).unwrap_err();
+        assert_eq!(e.to_string(), errors[0].to_string());
+
+        assert_eq!(root["a"][0].get_attribute("href").unwrap(), "x");
+        assert_eq!(root["b"][0].content, "text < more");
+        assert_eq!(root["c"][0]["d"][0].content, "unclosed");
+        assert!(root["f"][0].attributes.is_empty());
+        assert_eq!(root["f"][0].content, "two &nope; &");
+
+        // Unclosed nodes end with the input, and only the innermost one is reported
+        let (root, errors) =
+            szl_simple_xml::from_string_recovering("<a><b>text", Default::default());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position().unwrap().path, "a/b");
+        assert_eq!(root["b"][0].content, "text");
+
+        let (_, errors) =
+            szl_simple_xml::from_string_recovering("<r><a><b><c></r>", Default::default());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position().unwrap().path, "r/a/b/c");
+
+        let depth = 100_000;
+        let (root, errors) =
+            szl_simple_xml::from_string_recovering(&"<a>".repeat(depth), Default::default());
+        assert_eq!(errors.len(), 1);
+        let position = errors[0].position().unwrap();
+        assert_eq!(position.offset, 3 * (depth - 1));
+        assert_eq!(position.path.len(), 2 * depth - 1);
+        assert_eq!(root.tag, "a");
+
+        // Content outside the root node is positioned when recovering
+        let (root, errors) =
+            szl_simple_xml::from_string_recovering("<a/>\n<b/>", Default::default());
+        assert_eq!(root.tag, "a");
+        assert_eq!(errors[0].kind(), ErrorKind::ContentOutsideRoot);
+        assert_eq!(errors[0].position().unwrap().line, 2);
+    }
+}
@@ -25,18 +25,18 @@ mod tests {
         "Anthony",
     ];
 
-    fn create_person(num_friends: usize, depth: usize) -> simple_xml::Node {
-        let mut person = simple_xml::new("person", String::new());
-        person.add_node(simple_xml::new(
+    fn create_person(num_friends: usize, depth: usize) -> szl_simple_xml::Node {
+        let mut person = szl_simple_xml::new("person", String::new());
+        person.add_node(szl_simple_xml::new(
             "name",
             NAMES.choose(&mut rand::thread_rng()).unwrap().to_string(),
         ));
-        person.add_node(simple_xml::new("address", "Rose Walk 3".to_owned()));
-        let mut balance = simple_xml::new("balance", "5".to_owned());
+        person.add_node(szl_simple_xml::new("address", "Rose Walk 3".to_owned()));
+        let mut balance = szl_simple_xml::new("balance", "5".to_owned());
         balance.add_attribute("currency", "pound");
         person.add_node(balance);
 
-        let mut friends = simple_xml::new("friends", String::new());
+        let mut friends = szl_simple_xml::new("friends", String::new());
 
         if depth > 0 {
             for _ in 0..num_friends {
@@ -46,10 +46,39 @@ mod tests {
         person.add_node(friends);
         person
     }
+    /// Asserts that two person structures generated by create_person are equal
+    fn assert_same_person(a: &szl_simple_xml::Node, b: &szl_simple_xml::Node) {
+        assert_eq!(a["name"][0].content, b["name"][0].content);
+        assert_eq!(a["address"][0].content, b["address"][0].content);
+        assert_eq!(a["balance"][0].content, b["balance"][0].content);
+        assert_eq!(
+            a["balance"][0].attributes["currency"],
+            b["balance"][0].attributes["currency"]
+        );
+
+        let (a, b) = (&a["friends"][0]["person"], &b["friends"][0]["person"]);
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert_same_person(a, b);
+        }
+    }
+
     #[test]
 
     fn generate_person() {
         let person = create_person(10, 2);
         std::fs::write("./examples/person_gen.xml", person.to_string_pretty()).unwrap();
     }
+
+    #[test]
+    fn parse_generated_person() {
+        let person = create_person(4, 3);
+
+        let parsed = szl_simple_xml::from_string(&person.to_string()).expect("Failed to parse");
+        assert_same_person(&person, &parsed);
+
+        let parsed =
+            szl_simple_xml::from_string(&person.to_string_pretty()).expect("Failed to parse");
+        assert_same_person(&person, &parsed);
+    }
 }
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+    use szl_simple_xml::reader::Reader;
+    use szl_simple_xml::{Attributes, Error, ErrorKind, Handler, ParseOptions};
+
+    /// Collects the vertex positions of a COLLADA file without building a tree
+    #[derive(Default)]
+    struct Positions {
+        path: Vec<String>,
+        in_positions: bool,
+        floats: Vec<f32>,
+        elements: usize,
+        comments: usize,
+        errors: Vec<ErrorKind>,
+        ended: bool,
+    }
+
+    impl Handler for Positions {
+        fn start_element(&mut self, name: &str, attributes: &Attributes) {
+            self.elements += 1;
+            self.path.push(name.to_owned());
+            if name == "float_array" {
+                self.in_positions = attributes
+                    .get("id")
+                    .is_some_and(|id| id.ends_with("-positions-array"));
+            }
+        }
+
+        fn end_element(&mut self, name: &str) {
+            assert_eq!(self.path.pop().as_deref(), Some(name));
+            self.in_positions = false;
+        }
+
+        fn characters(&mut self, text: &str) {
+            if self.in_positions {
+                self.floats
+                    .extend(text.split_whitespace().map(|v| v.parse::<f32>().unwrap()));
+            }
+        }
+
+        fn comment(&mut self, _comment: &str) {
+            self.comments += 1;
+        }
+
+        fn error(&mut self, error: &Error) {
+            self.errors.push(error.kind());
+        }
+
+        fn end_document(&mut self) {
+            self.ended = true;
+        }
+    }
+
+    #[test]
+    fn handle_collada() {
+        let file = std::fs::File::open("./examples/cube.dae").unwrap();
+        let mut positions = Positions::default();
+        szl_simple_xml::parse_with_handler(file, &mut positions).expect("Failed to parse cube");
+
+        // 8 vertices of the cube
+        assert_eq!(positions.floats.len(), 24);
+        assert!(positions.floats.iter().all(|v| v.abs() == 1.0));
+        assert!(positions.path.is_empty());
+        assert!(positions.ended);
+
+        let root = szl_simple_xml::from_file("./examples/cube.dae").unwrap();
+        let mut count = 0;
+        let mut nodes = vec![&root];
+        while let Some(node) = nodes.pop() {
+            count += 1;
+            nodes.extend(node.items().filter_map(|item| match item {
+                szl_simple_xml::Item::Node(node) => Some(node),
+                _ => None,
+            }));
+        }
+        assert_eq!(positions.elements, count);
+    }
+
+    #[test]
+    fn handle_options_and_errors() {
+        let options = ParseOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        let mut positions = Positions::default();
+        Reader::with_options("<a><!-- one --><b/><!-- two --></a>".as_bytes(), options)
+            .handle(&mut positions)
+            .unwrap();
+        assert_eq!(positions.comments, 2);
+        assert_eq!(positions.elements, 2);
+
+        let mut positions = Positions::default();
+        let e = szl_simple_xml::parse_with_handler("<a><b></a>".as_bytes(), &mut positions)
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::MissingClosingTag);
+        assert_eq!(positions.errors, vec![ErrorKind::MissingClosingTag]);
+        assert!(!positions.ended);
+    }
+}
@@ -1,9 +1,13 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
+    use crate::common::{assert_deep, deep_xml};
+
     #[test]
     fn parse() {
         let note =
-            simple_xml::from_file("./examples/note.xml").expect("Failed to parse simple_xml");
+            szl_simple_xml::from_file("./examples/note.xml").expect("Failed to parse simple_xml");
 
         let to = &note["to"][0];
         let from = &note["from"][0];
@@ -15,8 +19,8 @@ mod tests {
 
         // Test try_get_nodes
         match note.try_get_nodes("missing_tag") {
-            Err(simple_xml::Error::TagNotFound(a, b)) if a == "note" && b == "missing_tag" => {}
-            Err(simple_xml::Error::TagNotFound(_, _)) => {
+            Err(szl_simple_xml::Error::TagNotFound(a, b)) if a == "note" && b == "missing_tag" => {}
+            Err(szl_simple_xml::Error::TagNotFound(_, _)) => {
                 panic!("Incorrect error for try_get_nodes()");
             }
             Err(_) => {
@@ -35,7 +39,7 @@ mod tests {
 
     #[test]
     fn parse_collada() {
-        let _root = match simple_xml::from_file("./examples/cube.dae") {
+        let _root = match szl_simple_xml::from_file("./examples/cube.dae") {
             Err(e) => {
                 println!("Error: {:?}", e);
                 panic!("")
@@ -47,7 +51,7 @@ mod tests {
     #[test]
     fn parse_graph() {
         let graph =
-            simple_xml::from_file("./examples/graph.xml").expect("Failed to parse graph.xml");
+            szl_simple_xml::from_file("./examples/graph.xml").expect("Failed to parse graph.xml");
 
         assert_eq!(graph["node"].len(), 4);
         assert_eq!(graph["node"][0].attributes["id"], "n1");
@@ -78,4 +82,583 @@ mod tests {
         assert_eq!(graph["edge"][4].attributes["from"], "n4");
         assert_eq!(graph["edge"][4].attributes["to"], "n3");
     }
+
+    #[test]
+    fn parse_comments() {
+        let xml = "<!-- before root -->
+<config>
+    <!-- a <disabled attr=\"x\"> block -> gone -->
+    <value>1</value>
+    <!--<value>2</value>-->
+</config>
+<!-- after root -->";
+
+        let config = szl_simple_xml::from_string(xml).expect("Failed to parse comments");
+        assert_eq!(config["value"].len(), 1);
+        assert_eq!(config["value"][0].content, "1");
+        assert_eq!(config.comments().count(), 0);
+        assert_eq!(config.content, "");
+
+        let options = szl_simple_xml::ParseOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        let config =
+            szl_simple_xml::from_string_with(xml, options).expect("Failed to parse comments");
+        assert_eq!(
+            config.comments().collect::<Vec<_>>(),
+            vec![
+                " a <disabled attr=\"x\"> block -> gone ",
+                "<value>2</value>"
+            ]
+        );
+
+        // Comments survive a parse/save cycle
+        let reparsed = szl_simple_xml::from_string_with(&config.to_string(), options).unwrap();
+        assert!(reparsed.comments().eq(config.comments()));
+        let reparsed =
+            szl_simple_xml::from_string_with(&config.to_string_pretty(), options).unwrap();
+        assert!(reparsed.comments().eq(config.comments()));
+        assert_eq!(reparsed["value"][0].content, "1");
+
+        match szl_simple_xml::from_string("<a><!-- unterminated </a>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::MissingCommentEnd,
+                _,
+            )) => {}
+            v => panic!("Expected MissingCommentEnd, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn parse_cdata() {
+        let script = szl_simple_xml::from_string(
+            "<script>  <![CDATA[ if (a < b && c > d) {} ]]>  </script>",
+        )
+        .expect("Failed to parse CDATA");
+        assert_eq!(script.content, " if (a < b && c > d) {} ");
+
+        let shader = szl_simple_xml::from_string(
+            "<shader>\n    uniform <![CDATA[<vec4>]]> color;\n</shader>",
+        )
+        .expect("Failed to parse CDATA");
+        assert_eq!(shader.content, "uniform <vec4> color;");
+
+        match szl_simple_xml::from_string("<a><![CDATA[ unterminated </a>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::MissingCDataEnd,
+                _,
+            )) => {}
+            v => panic!("Expected MissingCDataEnd, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn parse_entities() {
+        let xml =
+            "<p title=\"&quot;Hi&quot; &amp; &apos;bye&apos;\">1 &lt; 2 &gt; 0&#10;&#x1F600;</p>";
+        let p = szl_simple_xml::from_string(xml).expect("Failed to parse entities");
+        assert_eq!(p.content, "1 < 2 > 0\n\u{1F600}");
+        assert_eq!(p.attributes["title"], "\"Hi\" & 'bye'");
+
+        let options = szl_simple_xml::ParseOptions {
+            raw: true,
+            ..Default::default()
+        };
+        let p = szl_simple_xml::from_string_with(xml, options).expect("Failed to parse entities");
+        assert_eq!(p.content, "1 &lt; 2 &gt; 0&#10;&#x1F600;");
+        assert_eq!(
+            p.attributes["title"],
+            "&quot;Hi&quot; &amp; &apos;bye&apos;"
+        );
+
+        // CDATA is never decoded
+        let p = szl_simple_xml::from_string("<p><![CDATA[&amp;]]></p>").unwrap();
+        assert_eq!(p.content, "&amp;");
+
+        // Only characters allowed in xml can be referenced, and only with digits
+        assert_eq!(
+            szl_simple_xml::escape::unescape("&#9;&#x10FFFF;").unwrap(),
+            "\t\u{10FFFF}"
+        );
+        let references = [
+            "&#0;",
+            "&#x0;",
+            "&#1;",
+            "&#+65;",
+            "&#-65;",
+            "&#x+41;",
+            "&#;",
+            "&#x;",
+            "&#xD800;",
+            "&#xFFFE;",
+            "&#x110000;",
+            "&#99999999999;",
+            "&#6 5;",
+        ];
+        for reference in references.iter() {
+            assert_eq!(
+                szl_simple_xml::escape::unescape(reference),
+                Err(reference.to_string()),
+                "{}",
+                reference
+            );
+        }
+
+        match szl_simple_xml::from_string("<p>\n&nbsp;</p>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::InvalidEntity(entity),
+                position,
+            )) if entity == "&nbsp;" => assert_eq!(position.line, 2),
+            v => panic!("Expected InvalidEntity, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn parse_nested_same_tag() {
+        let div = szl_simple_xml::from_string("<div><div>inner</div>tail<div><div/></div></div>")
+            .expect("Failed to parse nested divs");
+        assert_eq!(div.content, "tail");
+        assert_eq!(div["div"].len(), 2);
+        assert_eq!(div["div"][0].content, "inner");
+        assert_eq!(div["div"][1]["div"].len(), 1);
+
+        match szl_simple_xml::from_string("<div><div>inner</div>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::MissingClosingTag(tag),
+                _,
+            )) if tag == "div" => {}
+            v => panic!("Expected MissingClosingTag, got {:?}", v),
+        }
+
+        match szl_simple_xml::from_string("<a><b></a></b>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::MissingClosingTag(tag),
+                _,
+            )) if tag == "b" => {}
+            v => panic!("Expected MissingClosingTag, got {:?}", v),
+        }
+
+        match szl_simple_xml::from_string("</a>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::UnexpectedClosingTag(tag),
+                _,
+            )) if tag == "a" => {}
+            v => panic!("Expected UnexpectedClosingTag, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn parse_document_order() {
+        use szl_simple_xml::Item;
+
+        let xml = "<p>Hello<b>big</b>world<!--note--><i>!</i><![CDATA[<x>]]><b>again</b></p>";
+        let options = szl_simple_xml::ParseOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        let p = szl_simple_xml::from_string_with(xml, options).expect("Failed to parse");
+        assert_eq!(p.content, "Helloworld<x>");
+        assert_eq!(p["b"][1].content, "again");
+
+        let items: Vec<_> = p
+            .items()
+            .map(|item| match item {
+                Item::Node(node) => format!("node {}", node.tag),
+                Item::Text(text) => format!("text {}", text),
+                Item::CData(data) => format!("cdata {}", data),
+                Item::Comment(comment) => format!("comment {}", comment),
+            })
+            .collect();
+        assert_eq!(
+            items,
+            [
+                "text Hello",
+                "node b",
+                "text world",
+                "comment note",
+                "node i",
+                "cdata <x>",
+                "node b"
+            ]
+        );
+        assert_eq!(p.to_string(), xml);
+
+        // Edited content replaces the original text
+        let mut p = p;
+        p.content = "changed".to_owned();
+        assert_eq!(
+            p.to_string(),
+            "<p><b>big</b><!--note--><i>!</i><b>again</b>changed</p>"
+        );
+
+        let graph = szl_simple_xml::from_file("./examples/graph.xml").expect("Failed to parse");
+        let ids = |graph: &szl_simple_xml::Node| -> Vec<String> {
+            graph
+                .items()
+                .filter_map(|item| match item {
+                    Item::Node(node) => {
+                        Some(node.attributes.get("id").unwrap_or(&node.tag).clone())
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(
+            ids(&graph),
+            ["n1", "n2", "n3", "n4", "init", "e1", "e2", "e3", "e4", "e5"]
+        );
+
+        let resaved = szl_simple_xml::from_string(&graph.to_string_pretty()).unwrap();
+        assert_eq!(ids(&resaved), ids(&graph));
+
+        // Removing a node leaves its siblings where they were
+        let mut r = szl_simple_xml::from_string("<r><a>1</a><b/><a>2</a></r>").unwrap();
+        r.get_mut_nodes("a").unwrap().remove(0);
+        assert_eq!(r.to_string(), "<r><b/><a>2</a></r>");
+        r.get_mut_nodes("a").unwrap()[0].content = "3".to_owned();
+        assert_eq!(r.children_mut().last().unwrap().content, "3");
+        r.get_mut_nodes("b").unwrap().clear();
+        r.add_new_node("c", String::new());
+        assert_eq!(r.to_string(), "<r><a>3</a><c/></r>");
+
+        // Whitespace between words is kept in mixed content, and only layout is removed
+        let xml = "<p>Hello <b>big</b> world</p>";
+        let p = szl_simple_xml::from_string(xml).unwrap();
+        assert_eq!(p.content, "Hello  world");
+        assert_eq!(p.to_string(), xml);
+        assert_eq!(p.to_string_pretty(), format!("{}\n", xml));
+        let p = szl_simple_xml::from_string("<p>\n  Hello <b>big</b> <i>bold</i>\n</p>").unwrap();
+        assert_eq!(p.to_string(), "<p>Hello <b>big</b> <i>bold</i></p>");
+        let list = szl_simple_xml::from_string("<list>\n  <a> x </a>\n  <b/>\n</list>").unwrap();
+        assert_eq!(list.to_string(), "<list><a>x</a><b/></list>");
+        let p = szl_simple_xml::from_string_borrowed(xml).unwrap();
+        assert_eq!(p.to_owned().to_string(), xml);
+    }
+
+    #[test]
+    fn parse_attribute_quoting() {
+        let node = szl_simple_xml::from_string(
+            "<node a='single' b = \"spaced\" c= 'say \"hi\"' d =\"it's\"\n    e\t=\t'' f=\"x = y\"/>",
+        )
+        .expect("Failed to parse attributes");
+
+        assert_eq!(node.attributes["a"], "single");
+        assert_eq!(node.attributes["b"], "spaced");
+        assert_eq!(node.attributes["c"], "say \"hi\"");
+        assert_eq!(node.attributes["d"], "it's");
+        assert_eq!(node.attributes["e"], "");
+        assert_eq!(node.attributes["f"], "x = y");
+        assert_eq!(
+            node.attributes.keys().collect::<Vec<_>>(),
+            ["a", "b", "c", "d", "e", "f"]
+        );
+
+        match szl_simple_xml::from_string("<node a='mismatched\"/>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::MissingQuotes(_),
+                _,
+            )) => {}
+            v => panic!("Expected MissingQuotes, got {:?}", v),
+        }
+
+        match szl_simple_xml::from_string("<node a =/>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::MissingQuotes(_),
+                _,
+            )) => {}
+            v => panic!("Expected MissingQuotes, got {:?}", v),
+        }
+
+        match szl_simple_xml::from_string("<node a='b'c='d'/>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::MissingWhitespace(attribute),
+                _,
+            )) => assert_eq!(attribute, "a='b'"),
+            v => panic!("Expected MissingWhitespace, got {:?}", v),
+        }
+
+        // When recovering, both attributes are kept
+        let (node, errors) = szl_simple_xml::from_string_recovering(
+            "<node a='b'c=\"d\" e='f'/>",
+            Default::default(),
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(node.to_string(), "<node a=\"b\" c=\"d\" e=\"f\"/>");
+    }
+
+    #[test]
+    fn parse_namespaces() {
+        const COLLADA: &str = "http://www.collada.org/2005/11/COLLADASchema";
+        const XSI: &str = "http://www.w3.org/2001/XMLSchema-instance";
+        let options = szl_simple_xml::ParseOptions {
+            namespaces: true,
+            ..Default::default()
+        };
+
+        let root = szl_simple_xml::from_file_with("./examples/cube.dae", options)
+            .expect("Failed to parse cube.dae");
+        assert_eq!(root.namespace(), Some(COLLADA));
+        assert_eq!(root.lookup_namespace("xsi"), Some(XSI));
+        let geometries = &root.get_nodes_ns(COLLADA, "library_geometries")[0];
+        assert_eq!(geometries.get_nodes_ns(COLLADA, "geometry").len(), 1);
+        assert!(geometries.get_nodes_ns(XSI, "geometry").is_empty());
+
+        // The same document using a prefix for the COLLADA namespace and a nested default namespace
+        let xml = format!(
+            "<c:COLLADA xmlns:c=\"{}\" xmlns:xsi=\"{}\" xsi:schemaLocation=\"here\">
+                <c:library_geometries><c:geometry id=\"a\"/></c:library_geometries>
+                <extra xmlns=\"urn:extra\"><c:geometry id=\"b\"/><inner/></extra>
+            </c:COLLADA>",
+            COLLADA, XSI
+        );
+        let root = szl_simple_xml::from_string_with(&xml, options).expect("Failed to parse");
+        assert_eq!(root.prefix(), Some("c"));
+        assert_eq!(root.local_name(), "COLLADA");
+        assert_eq!(root.namespace(), Some(COLLADA));
+        assert_eq!(
+            root.get_attribute_ns(XSI, "schemaLocation").unwrap(),
+            "here"
+        );
+
+        let geometries = &root.get_nodes_ns(COLLADA, "library_geometries")[0];
+        assert_eq!(
+            geometries.get_nodes_ns(COLLADA, "geometry")[0].attributes["id"],
+            "a"
+        );
+
+        let extra = &root["extra"][0];
+        assert_eq!(extra.namespace(), Some("urn:extra"));
+        assert_eq!(extra["inner"][0].namespace(), Some("urn:extra"));
+        assert_eq!(
+            extra.get_nodes_ns(COLLADA, "geometry")[0].attributes["id"],
+            "b"
+        );
+
+        match root.try_get_nodes_ns(XSI, "geometry") {
+            Err(szl_simple_xml::Error::TagNotFound(_, name)) => {
+                assert_eq!(name, format!("{{{}}}geometry", XSI))
+            }
+            v => panic!("Expected TagNotFound, got {:?}", v),
+        }
+
+        // Namespaces are not resolved by default
+        let root = szl_simple_xml::from_string(&xml).unwrap();
+        assert_eq!(root.namespace(), None);
+
+        match szl_simple_xml::from_string_with("<a><b:c/></a>", options) {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::UnboundPrefix(prefix),
+                _,
+            )) if prefix == "b" => {}
+            v => panic!("Expected UnboundPrefix, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let xml = "<?xml version=\"1.0\"?>
+<COLLADA>
+  <library_geometries>
+    <geometry id=\"Cube-mesh\">
+      <mesh>
+        <source id=\"positions\" name=missing/>
+      </mesh>
+    </geometry>
+  </library_geometries>
+</COLLADA>";
+
+        match szl_simple_xml::from_string(xml) {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::MissingQuotes(_),
+                position,
+            )) => {
+                assert_eq!(position.line, 6);
+                assert_eq!(position.column, 32);
+                assert_eq!(&xml[position.offset..position.offset + 12], "name=missing");
+                assert_eq!(
+                    position.path,
+                    "COLLADA/library_geometries/geometry/mesh/source"
+                );
+            }
+            v => panic!("Expected MissingQuotes, got {:?}", v),
+        }
+
+        match szl_simple_xml::from_string("<a>\n  <b>\u{e9}\u{e9} <c>\n</a>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::MissingClosingTag(tag),
+                position,
+            )) if tag == "c" => {
+                assert_eq!((position.line, position.column), (2, 9));
+                assert_eq!(position.offset, 14);
+                assert_eq!(position.path, "a/b/c");
+            }
+            v => panic!("Expected MissingClosingTag, got {:?}", v),
+        }
+
+        match szl_simple_xml::from_string("<a/>\n</b>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::UnexpectedClosingTag(_),
+                position,
+            )) => {
+                assert_eq!((position.line, position.column), (2, 3));
+                assert_eq!(position.path, "");
+            }
+            v => panic!("Expected UnexpectedClosingTag, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn parse_deep_nesting() {
+        let depth = 100_000;
+        let xml = deep_xml(depth);
+        let root = szl_simple_xml::from_string(&xml).expect("Failed to parse deep nesting");
+
+        assert_deep(&root, depth);
+        assert_eq!(root.to_string(), xml);
+        assert!(format!("{:?}", root).ends_with("children: [\"a\"] }"));
+
+        // Fields can be moved out, while the rest of the tree is still dropped one node at a time
+        let tag = root.tag;
+        assert_eq!(tag, "a");
+    }
+
+    #[test]
+    fn parse_markup_in_attributes() {
+        let root = szl_simple_xml::from_string("<a expr='x > 1' other=\"</a>\"><b/></a>")
+            .expect("Failed to parse markup in attributes");
+        assert_eq!(root.get_attribute("expr").unwrap(), "x > 1");
+        assert_eq!(root.get_attribute("other").unwrap(), "</a>");
+        assert_eq!(root["b"].len(), 1);
+
+        match szl_simple_xml::from_string("<a><></a>") {
+            Err(szl_simple_xml::Error::ParseError(
+                szl_simple_xml::ParseError::InvalidTagName(name),
+                _,
+            )) if name.is_empty() => {}
+            v => panic!("Expected InvalidTagName, got {:?}", v),
+        }
+
+        // A '<' at the end of the input is not dropped
+        for xml in ["<a/><", "<a/></", "<a><"] {
+            match szl_simple_xml::from_string(xml) {
+                Err(szl_simple_xml::Error::ParseError(
+                    szl_simple_xml::ParseError::InvalidTagName(name),
+                    position,
+                )) if name.is_empty() => assert_eq!(position.offset, xml.rfind('<').unwrap()),
+                v => panic!("Expected InvalidTagName for {}, got {:?}", xml, v),
+            }
+            let (_, errors) = szl_simple_xml::from_string_recovering(xml, Default::default());
+            assert!(!errors.is_empty(), "No errors for {}", xml);
+        }
+    }
+
+    #[test]
+    fn parse_paths() {
+        let mut root = szl_simple_xml::from_file("./examples/mutable.xml")
+            .expect("Failed to parse simple_xml");
+
+        let file = root.at("resources/resource/file[2]").expect("Missing file");
+        assert_eq!(file.get_attribute("href").unwrap(), "js/functions.js");
+        assert_eq!(
+            root.at("/resources[0]/resource/file/")
+                .unwrap()
+                .get_attribute("href")
+                .unwrap(),
+            "launchpage.html"
+        );
+        assert_eq!(root.at("").unwrap().tag, "manifest");
+        assert!(root.at("resources/resource/file[3]").is_none());
+        assert!(root.at("resources/missing/file").is_none());
+
+        root.at_mut("resources/resource/file[1]")
+            .expect("Missing file")
+            .add_attribute("href", "js/main.js");
+        assert_eq!(
+            root["resources"][0]["resource"][0]["file"][1]
+                .get_attribute("href")
+                .unwrap(),
+            "js/main.js"
+        );
+        assert!(root.at_mut("resources/resource[1]").is_none());
+
+        assert_eq!(
+            root.try_at("resources/resource/file[1]")
+                .unwrap()
+                .get_attribute("href")
+                .unwrap(),
+            "js/main.js"
+        );
+        match root.try_at("resources/resource/file[5]/part") {
+            Err(szl_simple_xml::Error::TagNotFound(found, step)) => {
+                assert_eq!(found, "manifest/resources/resource");
+                assert_eq!(step, "file[5]");
+            }
+            _ => panic!("Incorrect error for try_at()"),
+        }
+        let e = root.try_at("resources/resource[x]").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "node <manifest/resources> has no child <resource[x]>"
+        );
+    }
+
+    #[test]
+    fn parse_typed_values() {
+        let cube =
+            szl_simple_xml::from_file("./examples/cube.dae").expect("Failed to parse simple_xml");
+
+        let unit = cube.at("asset/unit").expect("Missing unit");
+        assert_eq!(unit.attr_as::<u32>("meter"), Some(1));
+        assert_eq!(unit.attr_as::<f64>("meter"), Some(1.0));
+        assert_eq!(unit.attr_as::<u32>("name"), None);
+        assert_eq!(unit.attr_as::<u32>("missing"), None);
+        assert_eq!(unit.try_attr_as::<String>("name").unwrap(), "meter");
+
+        let xfov = cube
+            .at("library_cameras/camera/optics/technique_common/perspective/xfov")
+            .expect("Missing xfov");
+        assert_eq!(xfov.content_as::<f32>(), Some(39.59775));
+        assert_eq!(xfov.content_as::<i32>(), None);
+
+        let color = cube
+            .at("library_lights/light/technique_common/point/color")
+            .expect("Missing color");
+        assert_eq!(color.content_as_list::<u16>(), Some(vec![1000, 1000, 1000]));
+        assert_eq!(color.content_as_list::<u8>(), None);
+
+        let positions = cube
+            .at("library_geometries/geometry/mesh/source/float_array")
+            .expect("Missing float_array");
+        let values: Vec<f32> = positions.try_content_as_list().unwrap();
+        assert_eq!(
+            values.len(),
+            positions.try_attr_as::<usize>("count").unwrap()
+        );
+        assert_eq!(&values[..6], &[1.0, 1.0, 1.0, 1.0, 1.0, -1.0]);
+
+        let root = szl_simple_xml::from_string("<a size=' 2 3 ' bad='1 x'> 7 </a>").unwrap();
+        assert_eq!(root.try_content_as::<u8>().unwrap(), 7);
+        assert_eq!(root.attr_as_list::<u8>("size"), Some(vec![2, 3]));
+        assert_eq!(root.attr_as_list::<u8>("missing"), None);
+
+        match root.try_attr_as_list::<u8>("bad") {
+            Err(szl_simple_xml::Error::InvalidValue(tag, key, value)) => {
+                assert_eq!(tag, "a");
+                assert_eq!(key.as_deref(), Some("bad"));
+                assert_eq!(value, "x");
+            }
+            _ => panic!("Incorrect error for try_attr_as_list()"),
+        }
+        let e = root.try_attr_as::<u8>("bad").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "attribute \"bad\" of node <a> has invalid value \"1 x\""
+        );
+        assert_eq!(e.kind(), szl_simple_xml::ErrorKind::InvalidValue);
+        let e = root.try_content_as::<bool>().unwrap_err();
+        assert_eq!(e.to_string(), "node <a> has invalid content \"7\"");
+        let e = root.try_attr_as::<u8>("missing").unwrap_err();
+        assert_eq!(e.kind(), szl_simple_xml::ErrorKind::AttributeNotFound);
+    }
 }
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use szl_simple_xml::push::PushParser;
+    use szl_simple_xml::reader::Event;
+    use szl_simple_xml::ParseOptions;
+
+    const STREAM: &str = "<?xml version='1.0'?>\
+        <stream:stream xmlns:stream='http://etherx.jabber.org/streams' xmlns='jabber:client' to='example.com'>\
+        <message to='romeo@example.net' type='chat'><body>Grüße &amp; 😀</body><!-- sent --></message>\
+        <presence/>\
+        <iq id='1'><query xmlns='jabber:iq:roster'/></iq>";
+
+    #[test]
+    fn push_nodes_at_any_split() {
+        let options = ParseOptions {
+            keep_comments: true,
+            namespaces: true,
+            ..Default::default()
+        };
+        let bytes = STREAM.as_bytes();
+
+        for size in 1..=bytes.len() {
+            let mut parser = PushParser::with_options(options);
+            let mut nodes = Vec::new();
+            for chunk in bytes.chunks(size) {
+                parser.feed(chunk).unwrap();
+                while let Some(node) = parser.next_node().unwrap() {
+                    nodes.push(node);
+                }
+            }
+
+            // The root stays open
+            let root = parser.root().expect("Missing root");
+            assert_eq!(root.tag, "stream:stream");
+            assert_eq!(root.get_attribute("to").unwrap(), "example.com");
+            assert_eq!(parser.depth(), 1);
+
+            let tags: Vec<_> = nodes.iter().map(|node| node.tag.as_str()).collect();
+            assert_eq!(tags, vec!["message", "presence", "iq"], "split {}", size);
+            assert_eq!(nodes[0]["body"][0].content, "Grüße & 😀");
+            assert_eq!(
+                nodes[0].to_string(),
+                "<message to=\"romeo@example.net\" type=\"chat\"><body>Grüße &amp; 😀</body><!-- sent --></message>"
+            );
+            assert_eq!(nodes[0].namespace(), Some("jabber:client"));
+            assert_eq!(nodes[2]["query"][0].namespace(), Some("jabber:iq:roster"));
+        }
+
+        // Whitespace is removed the same way as by from_string
+        let mut parser = PushParser::new();
+        parser
+            .feed(b"<r>\n <p>\n Hello <b>big</b> world\n</p>\n</r>")
+            .unwrap();
+        let p = parser.next_node().unwrap().expect("Missing p");
+        assert_eq!(p.to_string(), "<p>Hello <b>big</b> world</p>");
+        assert!(parser.next_node().unwrap().is_none());
+        assert_eq!(parser.root().unwrap().to_string(), "<r/>");
+
+        // Whitespace between words in the text of the root is kept
+        let xml = "<r>\n Hello <b/> world<x/> <![CDATA[!]]> ";
+        let mut parser = PushParser::new();
+        parser.feed(xml.as_bytes()).unwrap();
+        while parser.next_node().unwrap().is_some() {}
+        let expected = szl_simple_xml::from_string(&format!("{}</r>", xml)).unwrap();
+        assert_eq!(parser.root().unwrap().content, expected.content);
+        assert_eq!(parser.root().unwrap().content, "Hello  world !");
+    }
+
+    #[test]
+    fn push_events() {
+        let mut parser = PushParser::new();
+        parser.feed(b"<a><b x='1").unwrap();
+        assert_eq!(
+            parser.next_event().unwrap(),
+            Some(Event::StartElement {
+                name: "a".to_owned(),
+                attributes: Default::default(),
+            })
+        );
+        assert_eq!(parser.next_event().unwrap(), None);
+
+        parser.feed(b"'>text").unwrap();
+        assert!(matches!(
+            parser.next_event().unwrap(),
+            Some(Event::StartElement { .. })
+        ));
+        // Text may continue until the next tag
+        assert_eq!(parser.next_event().unwrap(), None);
+
+        parser.feed(b"</b></a>").unwrap();
+        parser.finish().unwrap();
+        let rest: Vec<_> = std::iter::from_fn(|| parser.next_event().unwrap())
+            .take(4)
+            .collect();
+        assert_eq!(
+            rest,
+            vec![
+                Event::Text("text".to_owned()),
+                Event::EndElement {
+                    name: "b".to_owned()
+                },
+                Event::EndElement {
+                    name: "a".to_owned()
+                },
+                Event::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn push_errors() {
+        let mut parser = PushParser::new();
+        parser.feed(b"<a><b></a>").unwrap();
+        let e = parser.next_node().unwrap_err();
+        assert_eq!(e.kind(), szl_simple_xml::ErrorKind::MissingClosingTag);
+
+        // The end of the input is only known after finish
+        let mut parser = PushParser::new();
+        parser.feed(b"<a><!-- open").unwrap();
+        assert!(parser.next_node().unwrap().is_none());
+        parser.finish().unwrap();
+        let e = parser.next_node().unwrap_err();
+        assert_eq!(e.kind(), szl_simple_xml::ErrorKind::MissingCommentEnd);
+
+        // Input left over after finish is an error rather than waiting for more
+        let mut parser = PushParser::new();
+        parser.feed(b"<a/><").unwrap();
+        assert!(parser.next_node().unwrap().is_none());
+        parser.finish().unwrap();
+        let e = parser.next_event().unwrap_err();
+        assert_eq!(e.kind(), szl_simple_xml::ErrorKind::InvalidTagName);
+        assert_eq!(parser.next_event().unwrap(), Some(Event::Eof));
+
+        let mut parser = PushParser::new();
+        assert!(parser.feed(&[b'<', 0xff]).is_err());
+        parser.feed("<a>\u{e9}".as_bytes()[..4].as_ref()).unwrap();
+        assert!(parser.finish().is_err());
+    }
+}
@@ -0,0 +1,149 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use std::io::Read;
+    use szl_simple_xml::reader::{Event, Reader};
+    use szl_simple_xml::{Node, ParseOptions};
+
+    /// Returns a single byte per read, splitting every token and character
+    struct OneByte<'a>(&'a [u8]);
+
+    impl Read for OneByte<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.split_first() {
+                Some((first, rest)) if !buf.is_empty() => {
+                    buf[0] = *first;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    /// Builds a tree from the events of a reader
+    fn build<R: Read>(reader: Reader<R>) -> Result<Node, szl_simple_xml::Error> {
+        let mut stack: Vec<Node> = Vec::new();
+        let mut root = None;
+        for event in reader {
+            match event? {
+                Event::StartElement { name, attributes } => {
+                    let mut node = szl_simple_xml::new(&name, String::new());
+                    node.attributes = attributes;
+                    stack.push(node);
+                }
+                Event::EndElement { .. } => {
+                    let node = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some(parent) => parent.add_node(node),
+                        None => root = Some(node),
+                    }
+                }
+                Event::Text(text) => stack.last_mut().unwrap().add_text(&text),
+                Event::CData(data) => stack.last_mut().unwrap().add_cdata(&data),
+                Event::Comment(comment) => stack.last_mut().unwrap().add_comment(&comment),
+                _ => {}
+            }
+        }
+        Ok(root.unwrap())
+    }
+
+    #[test]
+    fn read_events() {
+        let xml = "<?xml version=\"1.0\"?><a x='1 &amp; 2'>text &lt;<b/><![CDATA[<raw>]]></a>";
+        let events: Vec<_> = Reader::new(xml.as_bytes())
+            .collect::<Result<_, _>>()
+            .expect("Failed to read events");
+
+        let mut attributes = szl_simple_xml::Attributes::new();
+        attributes.insert("x".to_owned(), "1 & 2".to_owned());
+        assert_eq!(
+            events,
+            vec![
+                Event::ProcessingInstruction("xml".to_owned(), "version=\"1.0\"".to_owned()),
+                Event::StartElement {
+                    name: "a".to_owned(),
+                    attributes,
+                },
+                Event::Text("text <".to_owned()),
+                Event::StartElement {
+                    name: "b".to_owned(),
+                    attributes: Default::default(),
+                },
+                Event::EndElement {
+                    name: "b".to_owned()
+                },
+                Event::CData("<raw>".to_owned()),
+                Event::EndElement {
+                    name: "a".to_owned()
+                },
+            ]
+        );
+
+        let mut reader = Reader::new("<a/>".as_bytes());
+        while reader.next_event().unwrap() != Event::Eof {}
+        assert_eq!(reader.next_event().unwrap(), Event::Eof);
+    }
+
+    #[test]
+    fn read_matches_from_string() {
+        let options = ParseOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        common::for_each_example(|file, xml| {
+            let expected = szl_simple_xml::from_string_with(xml, options).unwrap();
+
+            let read = build(Reader::with_options(xml.as_bytes(), options)).unwrap();
+            assert_eq!(read.to_string(), expected.to_string(), "{}", file);
+
+            let read = build(Reader::with_options(OneByte(xml.as_bytes()), options)).unwrap();
+            assert_eq!(read.to_string(), expected.to_string(), "{}", file);
+        });
+
+        // Whitespace between words in mixed content is kept
+        let inputs = [
+            "<p>Hello <b>big</b> world</p>",
+            "<p>\n  one <b>two</b><i>three</i> &amp; four<![CDATA[ five ]]> \n</p>",
+        ];
+        for xml in inputs.iter() {
+            let expected = szl_simple_xml::from_string_with(xml, options).unwrap();
+            let read = build(Reader::with_options(OneByte(xml.as_bytes()), options)).unwrap();
+            assert_eq!(read.to_string(), expected.to_string(), "{}", xml);
+            assert_eq!(read.content, expected.content, "{}", xml);
+        }
+
+        // Characters split across reads
+        let xml = "\u{feff}<grüße><ö>😀 &#x1F600;</ö><!-- ✓ --></grüße>";
+        let read = build(Reader::with_options(OneByte(xml.as_bytes()), options)).unwrap();
+        assert_eq!(read.to_string(), "<grüße><ö>😀 😀</ö><!-- ✓ --></grüße>");
+    }
+
+    #[test]
+    fn read_errors_match_from_string() {
+        let inputs = [
+            "<a>\n  <b>\n</a>",
+            "<a>\n</b>",
+            "<a b=c/>",
+            "<a>&nope;</a>",
+            "<a><</a>",
+            "<a><!-- open",
+            "<a/><b/>",
+            "<a>",
+            "<a/><",
+            "<a/></",
+            "<a/><?xml version='1.0'?>",
+            "<?xml nope=\"1\"?><a/>",
+        ];
+        for xml in inputs.iter() {
+            let expected = szl_simple_xml::from_string(xml).unwrap_err();
+            let e = build(Reader::new(OneByte(xml.as_bytes()))).unwrap_err();
+            assert_eq!(e.to_string(), expected.to_string(), "{:?}", xml);
+        }
+
+        let e = build(Reader::new(&[b'<', b'a', b'>', 0xff][..])).unwrap_err();
+        assert_eq!(e.kind(), szl_simple_xml::ErrorKind::IO);
+    }
+}
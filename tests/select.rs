@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod tests {
+    use szl_simple_xml::select::Selector;
+    use szl_simple_xml::{ErrorKind, Node};
+
+    fn attributes<'a>(nodes: impl Iterator<Item = &'a Node>, key: &str) -> Vec<&'a str> {
+        nodes
+            .map(|node| node.get_attribute(key).unwrap().as_str())
+            .collect()
+    }
+
+    #[test]
+    fn select_collada() {
+        let cube = szl_simple_xml::from_file("./examples/cube.dae").expect("Failed to parse cube");
+        let select = |selector| cube.select(selector).unwrap();
+        let tags = |selector| -> Vec<&str> { select(selector).map(|n| n.tag.as_str()).collect() };
+
+        assert_eq!(
+            attributes(
+                select("library_geometries > geometry[id$='-mesh'] source"),
+                "id"
+            ),
+            vec![
+                "Cube-mesh-positions",
+                "Cube-mesh-normals",
+                "Cube-mesh-map-0"
+            ]
+        );
+        assert_eq!(tags("COLLADA"), vec!["COLLADA"]);
+        assert_eq!(tags(":root > asset > *:first-child"), vec!["contributor"]);
+        assert_eq!(select("library_geometries > source").count(), 0);
+        assert_eq!(
+            select("*").count(),
+            cube.select("COLLADA *").unwrap().count() + 1
+        );
+
+        assert_eq!(
+            attributes(select("accessor[source^='#Cube-mesh-n'] param"), "name"),
+            vec!["X", "Y", "Z"]
+        );
+        assert_eq!(
+            attributes(select("source[id*='map']"), "id"),
+            vec!["Cube-mesh-map-0"]
+        );
+        assert_eq!(
+            attributes(select("input[semantic=NORMAL]"), "source"),
+            vec!["#Cube-mesh-normals"]
+        );
+        assert_eq!(select("input[set]").count(), 1);
+        assert_eq!(select("[offset]").count(), 3);
+        assert_eq!(attributes(select("#Light"), "name"), vec!["Light"]);
+        assert_eq!(
+            attributes(select("node[type~=NODE][id|=Cube]"), "id"),
+            vec!["Cube"]
+        );
+
+        assert_eq!(
+            attributes(select("accessor > param:nth-child(2n+1)"), "name"),
+            vec!["X", "Z", "X", "Z", "S"]
+        );
+        assert_eq!(
+            attributes(select("accessor > param:nth-last-child(1)"), "name"),
+            vec!["Z", "Z", "T"]
+        );
+        assert_eq!(
+            attributes(select("visual_scene > node:first-of-type"), "id"),
+            vec!["Camera"]
+        );
+        assert_eq!(
+            attributes(select("visual_scene > node:nth-of-type(even)"), "id"),
+            vec!["Light"]
+        );
+        assert_eq!(
+            attributes(select("visual_scene > node:last-of-type"), "id"),
+            vec!["Cube"]
+        );
+        assert_eq!(
+            tags("perspective > xfov + aspect_ratio"),
+            vec!["aspect_ratio"]
+        );
+        assert_eq!(
+            tags("perspective > xfov ~ *"),
+            vec!["aspect_ratio", "znear", "zfar"]
+        );
+        assert_eq!(tags("mesh > vertices:only-of-type"), vec!["vertices"]);
+        assert_eq!(tags("unit:empty, up_axis"), vec!["unit", "up_axis"]);
+
+        let first = cube.select_first("source float_array").unwrap().unwrap();
+        assert_eq!(first.get_attribute("count").unwrap(), "24");
+        assert!(cube.select_first("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn select_reuse_and_errors() {
+        let graph =
+            szl_simple_xml::from_file("./examples/graph.xml").expect("Failed to parse graph");
+        let selector = Selector::new("node:nth-child(-n+2), edge[from=n3]").unwrap();
+        assert_eq!(
+            attributes(selector.select(&graph), "id"),
+            vec!["n1", "n2", "e3", "e4"]
+        );
+        // Selecting from a child node does not see its parent
+        let first = &graph["node"][0];
+        assert_eq!(attributes(selector.select(first), "id"), vec!["n1"]);
+        assert_eq!(graph.select("graph > node > label").unwrap().count(), 1);
+        assert_eq!(first.select("graph label").unwrap().count(), 0);
+
+        let root = szl_simple_xml::from_string("<a><xsi:type/></a>").unwrap();
+        assert_eq!(root.select("a > xsi\\:type").unwrap().count(), 1);
+
+        let selectors = [
+            "",
+            "a >",
+            "a[",
+            "a[b=",
+            "a[b='c]",
+            "a:hover",
+            "a:nth-child(x)",
+            "a,",
+            "a)",
+        ];
+        for selector in selectors.iter() {
+            match graph.select(selector) {
+                Err(e) => assert_eq!(e.kind(), ErrorKind::InvalidSelector, "{}", selector),
+                Ok(_) => panic!("Expected an error for {:?}", selector),
+            }
+        }
+        let e = graph.select_first("a:hover").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid selector \"a:hover\": unsupported pseudo-class :hover"
+        );
+    }
+}
@@ -0,0 +1,114 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::deep_xml;
+    use szl_simple_xml::Node;
+
+    fn ids<'a>(nodes: impl Iterator<Item = &'a Node>) -> Vec<&'a str> {
+        nodes
+            .filter_map(|node| node.get_attribute("id"))
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[test]
+    fn walk_graph() {
+        let graph =
+            szl_simple_xml::from_file("./examples/graph.xml").expect("Failed to parse graph");
+
+        let tags: Vec<_> = graph.children().map(|n| n.tag.as_str()).collect();
+        assert_eq!(
+            tags,
+            vec!["node", "node", "node", "node", "init", "edge", "edge", "edge", "edge", "edge"]
+        );
+
+        let mut descendants = graph.descendants();
+        let mut depths = Vec::new();
+        while let Some(node) = descendants.next() {
+            depths.push((node.tag.as_str(), descendants.depth()));
+        }
+        assert_eq!(depths[..3], [("node", 1), ("label", 2), ("node", 1)]);
+        assert_eq!(depths.len(), 11);
+
+        let mut breadth_first = graph.descendants_breadth_first();
+        let tags: Vec<_> = breadth_first.by_ref().map(|n| n.tag.as_str()).collect();
+        assert_eq!(tags.len(), 11);
+        assert_eq!(tags.last(), Some(&"label"));
+        assert_eq!(breadth_first.depth(), 2);
+
+        assert_eq!(
+            ids(graph.descendants_with_tag("edge")),
+            vec!["e1", "e2", "e3", "e4", "e5"]
+        );
+        let found = graph.find(|n| n.get_attribute("from").is_some_and(|v| v == "n3"));
+        assert_eq!(found.unwrap().get_attribute("id").unwrap(), "e3");
+        assert_eq!(
+            ids(graph.find_all(|n| n.get_attribute("to").is_some_and(|v| v == "n3"))),
+            vec!["e2", "e5"]
+        );
+        assert!(graph.find(|n| n.tag == "missing").is_none());
+        // The starting node itself is not part of the walk
+        assert!(graph.find(|n| n.tag == "graph").is_none());
+    }
+
+    #[test]
+    fn walk_mut() {
+        let mut cube =
+            szl_simple_xml::from_file("./examples/cube.dae").expect("Failed to parse cube");
+
+        let count = cube.children_mut().count();
+        assert_eq!(count, cube.children().count());
+
+        let xfov = cube.find_mut(|n| n.tag == "xfov").unwrap();
+        xfov.content = "45".to_owned();
+        assert_eq!(cube.find(|n| n.tag == "xfov").unwrap().content, "45");
+
+        for source in cube.find_all_mut(|n| n.tag == "source") {
+            source.add_attribute("checked", "true");
+        }
+        assert_eq!(
+            cube.descendants_with_tag("source")
+                .filter(|n| n.get_attribute("checked").is_some())
+                .count(),
+            3
+        );
+        // Nodes inside a match are not searched
+        assert_eq!(
+            cube.find_all_mut(|n| n.tag == "mesh" || n.tag == "source")
+                .count(),
+            1
+        );
+
+        let mut deepest = 0;
+        let mut visited = 0;
+        cube.walk_mut(|node, depth| {
+            deepest = deepest.max(depth);
+            visited += 1;
+            node.attributes.remove("sid");
+        });
+        assert_eq!(visited, cube.descendants().count());
+        let mut breadth_first = cube.descendants_breadth_first();
+        breadth_first.by_ref().for_each(drop);
+        assert_eq!(deepest, breadth_first.depth());
+        assert!(cube.find(|n| n.get_attribute("sid").is_some()).is_none());
+    }
+
+    #[test]
+    fn walk_deep_nesting() {
+        let depth = 100_000;
+        let xml = deep_xml(depth);
+        let mut root = szl_simple_xml::from_string(&xml).expect("Failed to parse deep nesting");
+
+        let mut descendants = root.descendants();
+        assert_eq!(descendants.by_ref().count(), depth - 1);
+        let mut breadth_first = root.descendants_breadth_first();
+        assert_eq!(breadth_first.by_ref().last().unwrap().content, "leaf");
+        assert_eq!(breadth_first.depth(), depth - 1);
+
+        let mut deepest = 0;
+        root.walk_mut(|_, depth| deepest = depth);
+        assert_eq!(deepest, depth - 1);
+        assert_eq!(root.find_mut(|n| n.content == "leaf").unwrap().tag, "a");
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use szl_simple_xml::WriteOptions;
+
+    #[test]
+    fn write_cdata() {
+        let mut shader = szl_simple_xml::new("shader", String::new());
+        shader.add_new_node("source", "if (a < b && c > d) { x = \"]]>\"; }".to_owned());
+        shader.add_new_node("name", "plain".to_owned());
+
+        let options = WriteOptions {
+            cdata: true,
+            ..Default::default()
+        };
+        let written = shader.to_string_with(options);
+        assert!(written.contains("<name>plain</name>"));
+
+        let read = szl_simple_xml::from_string(&written).expect("Failed to read back CDATA");
+        assert_eq!(read["source"][0].content, shader["source"][0].content);
+
+        let pretty = shader.to_string_with(WriteOptions {
+            pretty: true,
+            cdata: true,
+            ..Default::default()
+        });
+        assert!(pretty.contains(
+            "    <source><![CDATA[if (a < b && c > d) { x = \"]]]]><![CDATA[>\"; }]]></source>\n"
+        ));
+        let read = szl_simple_xml::from_string(&pretty).expect("Failed to read back CDATA");
+        assert_eq!(read["source"][0].content, shader["source"][0].content);
+    }
+
+    #[test]
+    fn write_entities() {
+        let mut book = szl_simple_xml::new("book", "Fish & <Chips>".to_owned());
+        book.add_attribute("title", "a \"b\" & c");
+
+        let written = book.to_string();
+        assert_eq!(
+            written,
+            "<book title=\"a &quot;b&quot; &amp; c\">Fish &amp; &lt;Chips&gt;</book>"
+        );
+
+        let read = szl_simple_xml::from_string(&written).expect("Failed to read back entities");
+        assert_eq!(read.content, book.content);
+        assert_eq!(read.attributes["title"], book.attributes["title"]);
+
+        let raw = book.to_string_with(WriteOptions {
+            raw: true,
+            ..Default::default()
+        });
+        assert_eq!(raw, "<book title=\"a \"b\" & c\">Fish & <Chips></book>");
+
+        // Whitespace which parsers would turn into spaces is escaped in attribute values
+        let mut node = szl_simple_xml::new("a", "line1\nline2\t".to_owned());
+        node.add_attribute("v", "line1\r\nline2\t");
+        let written = node.to_string();
+        assert_eq!(
+            written,
+            "<a v=\"line1&#13;&#10;line2&#9;\">line1\nline2\t</a>"
+        );
+        let read = szl_simple_xml::from_string(&written).unwrap();
+        assert_eq!(read.attributes["v"], node.attributes["v"]);
+    }
+
+    #[test]
+    fn write_comments() {
+        let mut node = szl_simple_xml::new("a", String::new());
+        node.add_comment("a--b---c-");
+        node.add_comment(" fine - ok ");
+        let written = node.to_string();
+        assert_eq!(written, "<a><!--a- -b- - -c- --><!-- fine - ok --></a>");
+
+        let options = szl_simple_xml::ParseOptions {
+            keep_comments: true,
+            ..Default::default()
+        };
+        let read = szl_simple_xml::from_string_with(&written, options)
+            .expect("Failed to read back comments");
+        assert_eq!(
+            read.comments().collect::<Vec<_>>(),
+            vec!["a- -b- - -c- ", " fine - ok "]
+        );
+
+        let mut document = szl_simple_xml::Document::new(node);
+        document
+            .epilog
+            .push(szl_simple_xml::Misc::Comment("end-".to_owned()));
+        assert!(document.to_string().ends_with("\n<!--end- -->"));
+        szl_simple_xml::Document::from_string_with(&document.to_string(), options)
+            .expect("Failed to read back document comments");
+    }
+
+    #[test]
+    fn write_attribute_order() {
+        let mut graph =
+            szl_simple_xml::from_file("./examples/graph.xml").expect("Failed to parse graph.xml");
+
+        let written = graph.to_string();
+        assert!(written.contains("<edge id=\"e4\" from=\"n3\" to=\"n4\"/>"));
+        let read = szl_simple_xml::from_string(&written).unwrap();
+        assert_eq!(read.to_string(), written);
+
+        // Updated attributes keep their place, new ones are appended
+        let edge = &mut graph.get_mut_nodes("edge").unwrap()[3];
+        edge.add_attribute("from", "n1");
+        edge.add_attribute("weight", "2");
+        assert_eq!(
+            edge.to_string(),
+            "<edge id=\"e4\" from=\"n1\" to=\"n4\" weight=\"2\"/>"
+        );
+
+        let sorted = edge.to_string_with(WriteOptions {
+            sort_attributes: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            sorted,
+            "<edge from=\"n1\" id=\"e4\" to=\"n4\" weight=\"2\"/>"
+        );
+
+        edge.attributes.remove("id");
+        assert_eq!(
+            edge.to_string(),
+            "<edge from=\"n1\" to=\"n4\" weight=\"2\"/>"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod tests {
+    use szl_simple_xml::xpath::{Match, Value, XPath};
+    use szl_simple_xml::ErrorKind;
+
+    fn ids(nodes: Vec<&szl_simple_xml::Node>) -> Vec<&str> {
+        nodes
+            .iter()
+            .map(|node| node.get_attribute("id").unwrap().as_str())
+            .collect()
+    }
+
+    #[test]
+    fn xpath_graph() {
+        let graph =
+            szl_simple_xml::from_file("./examples/graph.xml").expect("Failed to parse graph");
+        let select = |expression| graph.xpath_nodes(expression).unwrap();
+
+        assert_eq!(ids(select("//edge[@from='n3']")), vec!["e3", "e4"]);
+        assert_eq!(ids(select("/graph/node")), vec!["n1", "n2", "n3", "n4"]);
+        assert_eq!(ids(select("node")), vec!["n1", "n2", "n3", "n4"]);
+        assert_eq!(ids(select("node[2]")), vec!["n2"]);
+        assert_eq!(ids(select("node[last()]")), vec!["n4"]);
+        assert_eq!(ids(select("edge[position() > 3]")), vec!["e4", "e5"]);
+        assert_eq!(ids(select("(//edge)[1] | //node[label]")), vec!["n1", "e1"]);
+        assert_eq!(ids(select("//edge[@from='n3' and @to='n1']")), vec!["e3"]);
+        assert_eq!(ids(select("//edge[@to = //init/@ref]")), vec!["e3"]);
+        assert_eq!(ids(select("//node[not(@id='n1')][1]")), vec!["n2"]);
+        assert_eq!(ids(select("//label/..")), vec!["n1"]);
+        assert_eq!(ids(select("//label/ancestor::node")), vec!["n1"]);
+        assert_eq!(
+            ids(select("edge[1]/following-sibling::edge[1]")),
+            vec!["e2"]
+        );
+        assert_eq!(
+            ids(select("edge[3]/preceding-sibling::edge[1]")),
+            vec!["e2"]
+        );
+        assert_eq!(ids(select("//*[contains(@id, '5')]")), vec!["e5"]);
+        assert_eq!(
+            ids(select("child::node[starts-with(label, 'St')]")),
+            vec!["n1"]
+        );
+        assert!(select("//missing").is_empty());
+
+        let number = |expression| match graph.xpath(expression).unwrap() {
+            Value::Number(v) => v,
+            v => panic!("Expected a number for {}, got {:?}", expression, v),
+        };
+        assert_eq!(number("count(//edge)"), 5.0);
+        assert_eq!(number("count(//edge[@from='n3']) * 10 + 1"), 21.0);
+        assert_eq!(number("count(//@*) div 8"), 2.5);
+        assert_eq!(number("7 mod 3 - -1"), 2.0);
+        assert_eq!(number("string-length(//label)"), 5.0);
+        assert!(number("sum(//edge[1]/@id | //node[2]/@id)").is_nan());
+        assert!(number("number('x')").is_nan());
+
+        let string = |expression| graph.xpath(expression).unwrap().string();
+        assert_eq!(string("//label/text()"), "Start");
+        assert_eq!(string("name(/*)"), "graph");
+        assert_eq!(
+            string("concat(//edge[2]/@from, '-', //edge[2]/@to)"),
+            "n2-n3"
+        );
+        assert_eq!(string("substring('12345', 2, 3)"), "234");
+        assert_eq!(string("substring-after(//edge[1]/@id, 'e')"), "1");
+        assert_eq!(string("translate('abc', 'ab', 'B')"), "Bc");
+        assert_eq!(string("normalize-space('  a   b ')"), "a b");
+        assert_eq!(string("1 div 0"), "Infinity");
+        assert_eq!(string("0.5 + 1"), "1.5");
+        assert_eq!(string("round(2.5)"), "3");
+
+        assert!(graph.xpath("//edge[@from='n4']").unwrap().boolean());
+        assert!(!graph
+            .xpath("boolean(//edge[@from='n5'])")
+            .unwrap()
+            .boolean());
+        assert!(graph.xpath("count(node) = 4").unwrap().boolean());
+        assert!(graph.xpath("//edge/@id = 'e5'").unwrap().boolean());
+        assert!(graph.xpath("//edge/@id != 'e5'").unwrap().boolean());
+
+        match graph.xpath("//edge[1]/@from").unwrap() {
+            Value::Nodes(nodes) => match nodes.as_slice() {
+                [Match::Attribute("from", "n1")] => {}
+                v => panic!("Expected the from attribute, got {:?}", v),
+            },
+            v => panic!("Expected a node set, got {:?}", v),
+        }
+        match graph.xpath("/").unwrap() {
+            Value::Nodes(nodes) => assert!(matches!(nodes.as_slice(), [Match::Root(_)])),
+            v => panic!("Expected the root, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn xpath_collada() {
+        let cube = szl_simple_xml::from_file("./examples/cube.dae").expect("Failed to parse cube");
+
+        let sources = cube
+            .xpath_nodes("/COLLADA/library_geometries/geometry[@id='Cube-mesh']/mesh/source")
+            .unwrap();
+        assert_eq!(
+            ids(sources),
+            vec![
+                "Cube-mesh-positions",
+                "Cube-mesh-normals",
+                "Cube-mesh-map-0"
+            ]
+        );
+
+        let geometry = cube
+            .xpath_nodes("//source[@id='Cube-mesh-normals']/ancestor::geometry")
+            .unwrap();
+        assert_eq!(ids(geometry), vec!["Cube-mesh"]);
+
+        let xfov = cube.xpath("number(//perspective/xfov)").unwrap();
+        assert!((xfov.number() - 39.59775).abs() < 1e-9);
+        assert_eq!(cube.xpath("sum(//accessor/@count)").unwrap().number(), 50.0);
+        assert_eq!(
+            cube.xpath("count(//input[@semantic='VERTEX' or @semantic='NORMAL'])")
+                .unwrap()
+                .number(),
+            2.0
+        );
+        assert_eq!(
+            cube.xpath("string(//unit/@name)").unwrap().string(),
+            "meter"
+        );
+        assert_eq!(
+            cube.xpath_nodes("//node[matrix][last()]").unwrap()[0]
+                .get_attribute("id")
+                .unwrap(),
+            "Cube"
+        );
+        assert_eq!(
+            cube.xpath_nodes("//technique[@profile='blender']/*[. > 999]")
+                .unwrap()
+                .iter()
+                .map(|node| node.tag.as_str())
+                .collect::<Vec<_>>(),
+            vec!["energy", "bufsize"]
+        );
+
+        // Compiled expressions can be reused
+        let path = XPath::new("count(descendant::param)").unwrap();
+        let geometries = &cube["library_geometries"][0];
+        assert_eq!(path.evaluate(geometries).unwrap().number(), 8.0);
+        assert_eq!(path.evaluate(&cube).unwrap().number(), 8.0);
+    }
+
+    #[test]
+    fn xpath_errors() {
+        let root = szl_simple_xml::from_string("<a><b/></a>").unwrap();
+        let expressions = [
+            "//b[",
+            "unknown()",
+            "count()",
+            "$var",
+            "namespace::*",
+            "'open",
+            "b)",
+            "processing-instruction()",
+        ];
+        for expression in expressions.iter() {
+            let e = root.xpath(expression).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::InvalidXPath, "{}", expression);
+        }
+
+        let e = root.xpath_nodes("count(b)").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid xpath \"count(b)\": expression does not return a node set"
+        );
+        let e = root.xpath("count('b')").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid xpath \"count('b')\": argument of count() is not a node set"
+        );
+    }
+}